@@ -0,0 +1,421 @@
+//! Module for caching provider's responses on disk, next to the configuration file.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::config::APP_NAME;
+use crate::error::{Error, Result};
+use crate::provider::ProviderName;
+
+static CACHE_FOLDER: &str = "cache";
+
+/// Kind of provider's endpoint, which defines how long its responses stay fresh.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum_macros::Display, EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Endpoint {
+    /// Resolving address into coordinates.
+    Geocoding,
+    /// Current weather.
+    Current,
+    /// Weather for today and future dates, which might still change.
+    Forecast,
+    /// Weather for past dates.
+    History,
+}
+
+/// Time to live of cached responses per endpoint, in seconds.
+/// Omitted values fall back to the defaults, 'None' means, that responses of the endpoint never expire.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CacheTtl {
+    pub geocoding: Option<u64>,
+    pub current: Option<u64>,
+    pub forecast: Option<u64>,
+    pub history: Option<u64>,
+}
+
+impl Default for CacheTtl {
+    fn default() -> Self {
+        CacheTtl {
+            geocoding: Some(90 * 24 * 60 * 60),
+            current: Some(10 * 60),
+            forecast: Some(60 * 60),
+            history: None,
+        }
+    }
+}
+
+impl CacheTtl {
+    pub fn get(&self, endpoint: Endpoint) -> Option<u64> {
+        match endpoint {
+            Endpoint::Geocoding => self.geocoding,
+            Endpoint::Current => self.current,
+            Endpoint::Forecast => self.forecast,
+            Endpoint::History => self.history,
+        }
+    }
+}
+
+/// Identifies cached response by provider, endpoint, normalized address and date.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    provider: String,
+    endpoint: Endpoint,
+    address: String,
+    date: Option<String>,
+}
+
+impl CacheKey {
+    pub fn new(
+        provider_name: ProviderName,
+        endpoint: Endpoint,
+        address: &str,
+        date: Option<&str>,
+    ) -> CacheKey {
+        CacheKey {
+            provider: provider_name.get_pretty_name(),
+            endpoint,
+            address: normalize_address(address),
+            date: date.map(str::to_owned),
+        }
+    }
+
+    /// File name of the entry, stable across application builds.
+    fn file_name(&self) -> String {
+        let key = format!(
+            "{}|{}|{}|{}",
+            self.provider,
+            self.endpoint,
+            self.address,
+            self.date.as_deref().unwrap_or_default()
+        );
+        format!(
+            "{}-{}-{:016x}.json",
+            self.provider,
+            self.endpoint,
+            fnv1a(&key)
+        )
+    }
+}
+
+/// Freshness of the response, which the provider reports in its headers. Overrides the endpoint's time to live.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Freshness {
+    /// Unix time, when the response becomes stale.
+    pub expires_at: Option<u64>,
+    /// Value of 'Last-Modified' header, with which the stale response might be revalidated.
+    pub last_modified: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CacheEntry {
+    key: CacheKey,
+    stored_at: u64,
+    body: String,
+    #[serde(default)]
+    expires_at: Option<u64>,
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+/// Statistics of the cache contents.
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub expired: usize,
+    pub bytes: u64,
+    pub entries_per_endpoint: Vec<(Endpoint, usize)>,
+}
+
+/// On-disk cache of raw provider's responses.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    directory: PathBuf,
+    ttl: CacheTtl,
+}
+
+impl Cache {
+    /// Creates cache, which stores entries in the 'directory'.
+    pub fn new(directory: PathBuf, ttl: CacheTtl) -> Cache {
+        Cache { directory, ttl }
+    }
+
+    /// Creates cache, which stores entries in the application's configuration directory.
+    ///
+    /// # Errors:
+    /// Returns 'Config' error, if configuration directory can't be determined.
+    pub fn open(ttl: CacheTtl) -> Result<Cache> {
+        let config_path = confy::get_configuration_file_path(APP_NAME, None)?;
+        let directory = config_path
+            .parent()
+            .ok_or_else(|| Error::Config(String::from("Invalid configuration directory")))?
+            .join(CACHE_FOLDER);
+
+        Ok(Cache::new(directory, ttl))
+    }
+
+    pub fn directory(&self) -> &PathBuf {
+        &self.directory
+    }
+
+    /// Returns cached response, if it exists and hasn't expired yet.
+    pub fn get(&self, key: &CacheKey) -> Option<String> {
+        let entry = self.read_entry(&self.directory.join(key.file_name()))?;
+        if entry.key != *key || self.is_expired(&entry) {
+            return None;
+        }
+
+        Some(entry.body)
+    }
+
+    /// Returns cached response, even if it has expired, along with its 'Last-Modified' value,
+    /// so that the provider might confirm, that it's still valid.
+    pub fn get_stale(&self, key: &CacheKey) -> Option<(String, Option<String>)> {
+        let entry = self.read_entry(&self.directory.join(key.file_name()))?;
+        if entry.key != *key {
+            return None;
+        }
+
+        Some((entry.body, entry.last_modified))
+    }
+
+    /// Stores the response.
+    pub fn put(&self, key: &CacheKey, body: &str) -> Result<()> {
+        self.put_with_freshness(key, body, Freshness::default())
+    }
+
+    /// Stores the response, which stays fresh as long as the provider reported.
+    pub fn put_with_freshness(
+        &self,
+        key: &CacheKey,
+        body: &str,
+        freshness: Freshness,
+    ) -> Result<()> {
+        let entry = CacheEntry {
+            key: key.clone(),
+            stored_at: now(),
+            body: body.to_owned(),
+            expires_at: freshness.expires_at,
+            last_modified: freshness.last_modified,
+        };
+        let content = serde_json::to_string(&entry)
+            .map_err(|err| Error::Config(format!("Failed to serialize cache entry: {}", err)))?;
+
+        std::fs::create_dir_all(&self.directory).map_err(|err| self.io_error(err))?;
+        std::fs::write(self.directory.join(key.file_name()), content)
+            .map_err(|err| self.io_error(err))
+    }
+
+    /// Collects statistics of the stored entries.
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats {
+            entries_per_endpoint: Endpoint::iter().map(|endpoint| (endpoint, 0)).collect(),
+            ..CacheStats::default()
+        };
+
+        for path in self.entry_paths()? {
+            let Some(entry) = self.read_entry(&path) else {
+                continue;
+            };
+
+            stats.entries += 1;
+            stats.bytes += std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            if self.is_expired(&entry) {
+                stats.expired += 1;
+            }
+            if let Some((_, count)) = stats
+                .entries_per_endpoint
+                .iter_mut()
+                .find(|(endpoint, _)| *endpoint == entry.key.endpoint)
+            {
+                *count += 1;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Removes all stored entries and returns their number.
+    pub fn clear(&self) -> Result<usize> {
+        let paths = self.entry_paths()?;
+        for path in &paths {
+            std::fs::remove_file(path).map_err(|err| self.io_error(err))?;
+        }
+
+        Ok(paths.len())
+    }
+
+    fn entry_paths(&self) -> Result<Vec<PathBuf>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let paths = std::fs::read_dir(&self.directory)
+            .map_err(|err| self.io_error(err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension().and_then(|extension| extension.to_str()) == Some("json")
+            })
+            .collect();
+
+        Ok(paths)
+    }
+
+    fn read_entry(&self, path: &PathBuf) -> Option<CacheEntry> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        if let Some(expires_at) = entry.expires_at {
+            return now() >= expires_at;
+        }
+
+        match self.ttl.get(entry.key.endpoint) {
+            Some(ttl) => now().saturating_sub(entry.stored_at) >= ttl,
+            None => false,
+        }
+    }
+
+    fn io_error(&self, err: std::io::Error) -> Error {
+        Error::Config(format!(
+            "Failed to access cache directory {}: {}",
+            self.directory.display(),
+            err
+        ))
+    }
+}
+
+/// Normalizes address, so that spelling variants share the same cache entry.
+fn normalize_address(address: &str) -> String {
+    address
+        .split(',')
+        .map(|part| part.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+        .to_lowercase()
+}
+
+/// FNV-1a hash, which, unlike the std hasher, is stable across Rust releases.
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    static CACHE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    /// Creates cache in a unique temporary directory.
+    pub(crate) fn temporary_cache(ttl: CacheTtl) -> Cache {
+        let directory = std::env::temp_dir().join(format!(
+            "elastio_task_cache_{}_{}",
+            std::process::id(),
+            CACHE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::remove_dir_all(&directory).ok();
+        Cache::new(directory, ttl)
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(
+            normalize_address("  Mykolaiv,Lviv   oblast , UKRAINE "),
+            "mykolaiv, lviv oblast, ukraine"
+        );
+    }
+
+    #[test]
+    fn test_cache_put_get_clear() {
+        let cache = temporary_cache(CacheTtl::default());
+        let key = CacheKey::new(
+            ProviderName::WeatherApi,
+            Endpoint::History,
+            "Lviv, Ukraine",
+            Some("2023-04-06"),
+        );
+        let other_key = CacheKey::new(
+            ProviderName::OpenWeatherMap,
+            Endpoint::History,
+            "Lviv, Ukraine",
+            Some("2023-04-06"),
+        );
+
+        assert_eq!(cache.get(&key), None);
+        cache.put(&key, "{}").unwrap();
+        assert_eq!(cache.get(&key).as_deref(), Some("{}"));
+        assert_eq!(cache.get(&other_key), None);
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.expired, 0);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert_eq!(cache.get(&key), None);
+    }
+
+    #[test]
+    fn test_cache_expired_entry() {
+        let cache = temporary_cache(CacheTtl {
+            current: Some(0),
+            ..CacheTtl::default()
+        });
+        let key = CacheKey::new(ProviderName::WeatherApi, Endpoint::Current, "Lviv", None);
+
+        cache.put(&key, "{}").unwrap();
+        assert_eq!(cache.get(&key), None);
+        assert_eq!(cache.stats().unwrap().expired, 1);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_cache_freshness() {
+        let cache = temporary_cache(CacheTtl {
+            forecast: Some(0),
+            ..CacheTtl::default()
+        });
+        let key = CacheKey::new(ProviderName::MetNo, Endpoint::Forecast, "Lviv", None);
+
+        // Reported expiration overrides the time to live.
+        let freshness = Freshness {
+            expires_at: Some(now() + 60),
+            last_modified: Some(String::from("Fri, 07 Apr 2023 12:00:00 GMT")),
+        };
+        cache.put_with_freshness(&key, "{}", freshness).unwrap();
+        assert_eq!(cache.get(&key).as_deref(), Some("{}"));
+
+        let freshness = Freshness {
+            expires_at: Some(now() - 1),
+            last_modified: Some(String::from("Fri, 07 Apr 2023 12:00:00 GMT")),
+        };
+        cache.put_with_freshness(&key, "{}", freshness).unwrap();
+        assert_eq!(cache.get(&key), None);
+        assert_eq!(
+            cache.get_stale(&key),
+            Some((
+                String::from("{}"),
+                Some(String::from("Fri, 07 Apr 2023 12:00:00 GMT"))
+            ))
+        );
+        cache.clear().unwrap();
+    }
+}
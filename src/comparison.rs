@@ -0,0 +1,323 @@
+//! Module for lining up weather reports of different providers side by side.
+use std::fmt::Display;
+
+use crate::provider::{ProviderName, Weather};
+use crate::units::{Quantity, Units};
+
+/// Mark, which highlights the fields, on which providers disagree.
+static DIVERGENCE_MARK: &str = "!";
+
+/// Table of normalized weather fields, reported by several providers, with spread between them.
+#[derive(Debug)]
+pub struct Comparison {
+    providers: Vec<ProviderName>,
+    rows: Vec<ComparisonRow>,
+}
+
+/// Single field of the comparison table.
+#[derive(Debug, PartialEq)]
+pub struct ComparisonRow {
+    pub field: String,
+    /// Values per provider, in the order of providers, '-' if provider doesn't report the field.
+    pub values: Vec<String>,
+    /// Difference between the highest and the lowest value, for numeric fields.
+    pub spread: Option<f64>,
+    /// Whether the spread exceeds the field's threshold, or providers report different conditions.
+    pub diverges: bool,
+}
+
+impl Comparison {
+    /// Builds comparison table from the reports of the providers, with values in the 'units' system.
+    pub fn new(reports: &[(ProviderName, Weather)], units: Units) -> Comparison {
+        let weathers: Vec<&Weather> = reports.iter().map(|(_, weather)| weather).collect();
+        let quantity = |name: &str, quantity| {
+            NumericField::new(format!("{}, {}", name, units.label(quantity)), units)
+                .with_quantity(quantity)
+        };
+
+        let rows = vec![
+            quantity("temperature", Quantity::Temperature)
+                .row(&weathers, 2.0, |weather| Some(weather.temperature)),
+            quantity("feels like", Quantity::Temperature)
+                .row(&weathers, 2.0, |weather| weather.feels_like),
+            NumericField::new(String::from("humidity, %"), units)
+                .row(&weathers, 15.0, |weather| weather.humidity.map(f64::from)),
+            quantity("pressure", Quantity::Pressure)
+                .row(&weathers, 5.0, |weather| weather.pressure),
+            quantity("precipitation", Quantity::Precipitation)
+                .row(&weathers, 2.0, |weather| weather.precipitation),
+            quantity("visibility", Quantity::Visibility)
+                .row(&weathers, 5.0, |weather| weather.visibility),
+            quantity("wind speed", Quantity::Speed)
+                .row(&weathers, 3.0, |weather| weather.wind.speed),
+            direction_row("wind direction, °", &weathers, 45.0),
+            condition_row("condition", &weathers),
+        ];
+
+        Comparison {
+            providers: reports.iter().map(|(name, _)| *name).collect(),
+            rows,
+        }
+    }
+
+    pub fn rows(&self) -> &[ComparisonRow] {
+        &self.rows
+    }
+}
+
+/// Numeric field of the comparison table, which values might be converted into the unit system.
+struct NumericField {
+    field: String,
+    units: Units,
+    quantity: Option<Quantity>,
+}
+
+impl NumericField {
+    fn new(field: String, units: Units) -> NumericField {
+        NumericField {
+            field,
+            units,
+            quantity: None,
+        }
+    }
+
+    fn with_quantity(mut self, quantity: Quantity) -> NumericField {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    /// Builds row of the field. Divergence is checked against the metric 'threshold', before the conversion.
+    fn row(
+        self,
+        weathers: &[&Weather],
+        threshold: f64,
+        value: impl Fn(&Weather) -> Option<f64>,
+    ) -> ComparisonRow {
+        let metric_values: Vec<Option<f64>> =
+            weathers.iter().map(|weather| value(weather)).collect();
+        let diverges = spread(&metric_values).is_some_and(|spread| spread > threshold);
+
+        let values: Vec<Option<f64>> = metric_values
+            .into_iter()
+            .map(|value| {
+                value.map(|value| match self.quantity {
+                    Some(quantity) => self.units.convert(quantity, value),
+                    None => value,
+                })
+            })
+            .collect();
+
+        ComparisonRow {
+            field: self.field,
+            spread: spread(&values),
+            values: values.into_iter().map(format_value).collect(),
+            diverges,
+        }
+    }
+}
+
+/// Difference between the highest and the lowest of the present values.
+fn spread(values: &[Option<f64>]) -> Option<f64> {
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+
+    match present.len() {
+        0 | 1 => None,
+        _ => {
+            let max = present.iter().copied().fold(f64::MIN, f64::max);
+            let min = present.iter().copied().fold(f64::MAX, f64::min);
+            Some(max - min)
+        }
+    }
+}
+
+/// Builds row for wind direction, which spread is measured along the circle, so 350° and 10° differ by 20°.
+fn direction_row(field: &str, weathers: &[&Weather], threshold: f64) -> ComparisonRow {
+    let values: Vec<Option<f64>> = weathers
+        .iter()
+        .map(|weather| weather.wind.direction.map(f64::from))
+        .collect();
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+
+    let spread = match present.len() {
+        0 | 1 => None,
+        _ => present
+            .iter()
+            .flat_map(|first| {
+                present.iter().map(move |second| {
+                    let difference = (first - second).abs() % 360.0;
+                    difference.min(360.0 - difference)
+                })
+            })
+            .reduce(f64::max),
+    };
+
+    ComparisonRow {
+        field: field.to_owned(),
+        values: values.into_iter().map(format_value).collect(),
+        spread,
+        diverges: spread.is_some_and(|spread| spread > threshold),
+    }
+}
+
+fn condition_row(field: &str, weathers: &[&Weather]) -> ComparisonRow {
+    let diverges = weathers
+        .windows(2)
+        .any(|pair| pair[0].condition.kind != pair[1].condition.kind);
+
+    ComparisonRow {
+        field: field.to_owned(),
+        values: weathers
+            .iter()
+            .map(|weather| weather.condition.kind.to_string())
+            .collect(),
+        spread: None,
+        diverges,
+    }
+}
+
+fn format_value(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.1}", value),
+        None => String::from("-"),
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut header = vec![String::from("field")];
+        header.extend(self.providers.iter().map(ProviderName::get_pretty_name));
+        header.push(String::from("spread"));
+
+        let mut lines = vec![header];
+        for row in &self.rows {
+            let mut line = vec![row.field.clone()];
+            line.extend(row.values.iter().cloned());
+            let spread = match row.spread {
+                Some(spread) => format!("{:.1}", spread),
+                None => String::from("-"),
+            };
+            line.push(match row.diverges {
+                true => format!("{} {}", spread, DIVERGENCE_MARK),
+                false => spread,
+            });
+            lines.push(line);
+        }
+
+        let widths: Vec<usize> = (0..lines[0].len())
+            .map(|column| {
+                lines
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        for line in lines {
+            let cells: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+                })
+                .collect();
+            writeln!(f, "{}", cells.join("  ").trim_end())?;
+        }
+
+        write!(
+            f,
+            "'{}' marks fields, on which providers disagree.",
+            DIVERGENCE_MARK
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::provider::{Condition, ConditionKind, Place, Wind};
+
+    fn weather(temperature: f64, direction: Option<u16>, kind: ConditionKind) -> Weather {
+        Weather {
+            location: Place {
+                name: String::from("Lviv"),
+                region: None,
+                country: None,
+                latitude: 49.84,
+                longitude: 24.03,
+                timezone: None,
+            },
+            observed_at: Utc::now(),
+            temperature,
+            feels_like: None,
+            humidity: Some(70),
+            pressure: Some(1015.0),
+            precipitation: None,
+            visibility: None,
+            wind: Wind {
+                speed: Some(3.0),
+                direction,
+            },
+            condition: Condition {
+                kind,
+                description: kind.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_comparison_rows() {
+        let reports = [
+            (
+                ProviderName::OpenWeatherMap,
+                weather(10.0, Some(350), ConditionKind::Rain),
+            ),
+            (
+                ProviderName::WeatherApi,
+                weather(14.5, Some(10), ConditionKind::Cloudy),
+            ),
+        ];
+        let comparison = Comparison::new(&reports, Units::Metric);
+        let rows = comparison.rows();
+
+        assert_eq!(
+            rows[0],
+            ComparisonRow {
+                field: String::from("temperature, °C"),
+                values: vec![String::from("10.0"), String::from("14.5")],
+                spread: Some(4.5),
+                diverges: true,
+            }
+        );
+        assert_eq!(rows[1].values, vec!["-", "-"]);
+        assert_eq!(rows[1].spread, None);
+        assert!(!rows[2].diverges);
+        assert_eq!(rows[7].spread, Some(20.0));
+        assert!(!rows[7].diverges);
+        assert!(rows[8].diverges);
+
+        let comparison = Comparison::new(&reports, Units::Imperial);
+        let rows = comparison.rows();
+        assert_eq!(rows[0].field, "temperature, °F");
+        assert_eq!(rows[0].values, vec!["50.0", "58.1"]);
+        assert!(rows[0].diverges);
+        assert_eq!(rows[3].field, "pressure, inHg");
+    }
+
+    #[test]
+    fn test_comparison_display() {
+        let comparison = Comparison::new(
+            &[(
+                ProviderName::WeatherApi,
+                weather(10.0, None, ConditionKind::Clear),
+            )],
+            Units::Metric,
+        );
+        let table = comparison.to_string();
+
+        assert!(table.starts_with("field              weather-api  spread\n"));
+        assert!(table.contains("temperature, °C    10.0         -\n"));
+    }
+}
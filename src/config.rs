@@ -0,0 +1,354 @@
+//! Module for persisting application configuration and provider's api keys.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::CacheTtl;
+use crate::error::{Error, Result};
+use crate::moment::Moment;
+use crate::provider::met_no::Product;
+use crate::provider::{Coordinates, Place, ProviderName};
+use crate::units::Units;
+
+pub(crate) static APP_NAME: &str = "ELASTIO_TASK";
+static SECRETS_NAME: &str = "secrets";
+
+/// Structure for retrieving stored provider and settings from configuration file.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct ApplicationConfig {
+    pub provider_name: ProviderName,
+    /// Providers, which are tried in order, when the current provider fails.
+    #[serde(default)]
+    pub fallback: Vec<ProviderName>,
+    /// Default unit system of the weather output.
+    #[serde(default)]
+    pub units: Units,
+    /// Locationforecast product, which met-no provider requests.
+    #[serde(default)]
+    pub met_no_product: Product,
+    /// Time to live of cached responses per endpoint, in seconds.
+    #[serde(default)]
+    pub cache_ttl: CacheTtl,
+    /// Address, alias of the saved place or coordinates, which weather is retrieved for, when the address isn't specified.
+    #[serde(default)]
+    pub default_location: Option<String>,
+    /// Saved places by their aliases, which might be used instead of the address.
+    #[serde(default)]
+    pub places: BTreeMap<String, Place>,
+}
+
+impl ApplicationConfig {
+    /// Loads configuration file, or creates the default one.
+    pub fn load() -> Result<Self> {
+        confy::load(APP_NAME, None)
+            .map_err(|err| Error::Config(format!("Failed to retrieve config: {}", err)))
+    }
+
+    /// Returns the current provider followed by the fallback providers, without repetitions.
+    pub fn provider_chain(&self) -> Vec<ProviderName> {
+        self.provider_chain_from(self.provider_name)
+    }
+
+    /// Returns the 'provider_name' followed by the fallback providers, without repetitions.
+    pub fn provider_chain_from(&self, provider_name: ProviderName) -> Vec<ProviderName> {
+        let mut chain = vec![provider_name];
+        for provider_name in &self.fallback {
+            if !chain.contains(provider_name) {
+                chain.push(*provider_name);
+            }
+        }
+
+        chain
+    }
+
+    /// Saves the 'place' under the 'alias'.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if the alias is empty, looks like coordinates or a date, or is already used.
+    pub fn add_place(&mut self, alias: String, place: Place) -> Result<()> {
+        self.check_alias(&alias)?;
+        self.places.insert(alias, place);
+        Ok(())
+    }
+
+    /// Saves the place, which is saved under the 'alias', under the 'new_alias' instead.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if there is no place under the 'alias', or the 'new_alias' can't be used.
+    pub fn rename_place(&mut self, alias: &str, new_alias: String) -> Result<()> {
+        self.check_alias(&new_alias)?;
+        let place = self.remove_place(alias)?;
+        self.places.insert(new_alias, place);
+        Ok(())
+    }
+
+    /// Removes the place, which is saved under the 'alias', and returns it.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if there is no place under the 'alias'.
+    pub fn remove_place(&mut self, alias: &str) -> Result<Place> {
+        self.places.remove(alias).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "There is no place saved as '{}'. Saved places are displayed by 'places list'",
+                alias
+            ))
+        })
+    }
+
+    fn check_alias(&self, alias: &str) -> Result<()> {
+        if alias.trim().is_empty()
+            || alias.parse::<Coordinates>().is_ok()
+            || alias.parse::<Moment>().is_ok()
+        {
+            return Err(Error::InvalidInput(format!(
+                "'{}' can't be used as an alias, use a name instead",
+                alias
+            )));
+        }
+        if self.places.contains_key(alias) {
+            return Err(Error::InvalidInput(format!(
+                "Alias '{}' is already used, rename or remove the saved place first",
+                alias
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Stores configuration file.
+    pub fn store(&self) -> Result<()> {
+        confy::store(APP_NAME, None, self).map_err(|err| {
+            Error::Config(format!(
+                "There was an issue while updating configuration file: {}",
+                err
+            ))
+        })
+    }
+}
+
+/// Structure for provider's api keys, stored in a secrets file next to the configuration file.
+/// Keys are mapped by the names of respective environment variables, e.g. 'WEATHER_API'.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct SecretsConfig {
+    api_keys: BTreeMap<String, String>,
+}
+
+impl SecretsConfig {
+    /// Loads secrets file, or creates the default one.
+    pub fn load() -> Result<Self> {
+        confy::load(APP_NAME, Some(SECRETS_NAME))
+            .map_err(|err| Error::Config(format!("Failed to retrieve secrets file: {}", err)))
+    }
+
+    /// Stores secrets file, which is made readable and writable only by its owner before the keys are written.
+    pub fn store(&self) -> Result<()> {
+        let path = confy::get_configuration_file_path(APP_NAME, Some(SECRETS_NAME))?;
+        restrict_permissions(&path)?;
+
+        confy::store_path(&path, self).map_err(|err| {
+            Error::Config(format!(
+                "There was an issue while updating secrets file: {}",
+                err
+            ))
+        })
+    }
+
+    pub fn get(&self, provider_name: ProviderName) -> Option<&str> {
+        self.api_keys
+            .get(&provider_name.to_string())
+            .map(String::as_str)
+    }
+
+    pub fn set(&mut self, provider_name: ProviderName, api_key: String) {
+        self.api_keys.insert(provider_name.to_string(), api_key);
+    }
+
+    pub fn remove(&mut self, provider_name: ProviderName) -> Option<String> {
+        self.api_keys.remove(&provider_name.to_string())
+    }
+}
+
+/// Where the api key of a provider comes from.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ApiKeySource {
+    Environment,
+    Secrets,
+    /// Provider needs no api key, so the key is empty.
+    NotRequired,
+}
+
+/// Resolves api key of the provider. Environment variable, named after the provider, overrides the stored key.
+/// Keyless providers get an empty key.
+///
+/// # Errors:
+/// Returns 'Credentials' error, naming the missing variable, in case the key is set nowhere.
+pub(crate) fn get_api_key(
+    provider_name: ProviderName,
+    secrets: &SecretsConfig,
+) -> Result<(String, ApiKeySource)> {
+    if !provider_name.requires_api_key() {
+        return Ok((String::new(), ApiKeySource::NotRequired));
+    }
+
+    if let Ok(api_key) = std::env::var(provider_name.to_string()) {
+        return Ok((api_key, ApiKeySource::Environment));
+    }
+
+    match secrets.get(provider_name) {
+        Some(api_key) => Ok((api_key.to_owned(), ApiKeySource::Secrets)),
+        None => Err(Error::Credentials(format!(
+            "Missing api key for {} provider. Store it with 'keys set {} <api_key>', or set {} environment variable",
+            provider_name.get_pretty_name(),
+            provider_name.get_pretty_name(),
+            provider_name
+        ))),
+    }
+}
+
+/// Masks api key for displaying, leaving only a few edge characters visible.
+pub(crate) fn mask_api_key(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.chars().collect();
+    if chars.len() < 12 {
+        return "*".repeat(chars.len());
+    }
+
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}{}", head, "*".repeat(chars.len() - 8), tail)
+}
+
+/// Creates the file with permissions of its owner only, or restricts permissions of the existing one,
+/// so that its content is never readable by others.
+#[cfg(unix)]
+fn restrict_permissions(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let restrict = || -> std::io::Result<()> {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(path)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+    };
+
+    restrict().map_err(|err| {
+        Error::Config(format!(
+            "Failed to restrict permissions of {}: {}",
+            path.display(),
+            err
+        ))
+    })
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &std::path::Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restrict_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "elastio_task_secrets_{}/secrets.toml",
+            std::process::id()
+        ));
+        std::fs::remove_file(&path).ok();
+
+        // The file is created restricted, and the existing one is restricted before it's rewritten.
+        restrict_permissions(&path).unwrap();
+        let mode = |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode(&path) & 0o777, 0o600);
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        restrict_permissions(&path).unwrap();
+        assert_eq!(mode(&path) & 0o777, 0o600);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_mask_api_key() {
+        assert_eq!(mask_api_key("0123456789abcdef"), "0123********cdef");
+        assert_eq!(mask_api_key("short"), "*****");
+    }
+
+    #[test]
+    fn test_provider_chain() {
+        let config = ApplicationConfig {
+            provider_name: ProviderName::WeatherApi,
+            fallback: vec![
+                ProviderName::WeatherApi,
+                ProviderName::OpenWeatherMap,
+                ProviderName::OpenWeatherMap,
+            ],
+            ..ApplicationConfig::default()
+        };
+
+        assert_eq!(
+            config.provider_chain(),
+            vec![ProviderName::WeatherApi, ProviderName::OpenWeatherMap]
+        );
+        assert_eq!(
+            config.provider_chain_from(ProviderName::OpenWeatherMap),
+            vec![ProviderName::OpenWeatherMap, ProviderName::WeatherApi]
+        );
+    }
+
+    #[test]
+    fn test_places() {
+        let mut config = ApplicationConfig::default();
+        let place = Place::at(Coordinates::new(49.84, 24.03).unwrap());
+
+        assert!(config
+            .add_place(String::from("office"), place.clone())
+            .is_ok());
+        assert!(matches!(
+            config.add_place(String::from("office"), place.clone()),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            config.add_place(String::from("49.84,24.03"), place.clone()),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            config.add_place(String::from("tomorrow"), place.clone()),
+            Err(Error::InvalidInput(_))
+        ));
+
+        assert!(config.rename_place("office", String::from("work")).is_ok());
+        assert!(!config.places.contains_key("office"));
+        assert!(matches!(
+            config.rename_place("office", String::from("home")),
+            Err(Error::InvalidInput(_))
+        ));
+
+        assert_eq!(config.remove_place("work").unwrap(), place);
+        assert!(config.places.is_empty());
+    }
+
+    #[test]
+    fn test_secrets_config() {
+        let mut secrets = SecretsConfig::default();
+        secrets.set(ProviderName::WeatherApi, String::from("stored_key"));
+        assert_eq!(secrets.get(ProviderName::WeatherApi), Some("stored_key"));
+        assert_eq!(secrets.get(ProviderName::OpenWeatherMap), None);
+
+        assert_eq!(
+            secrets.remove(ProviderName::WeatherApi).as_deref(),
+            Some("stored_key")
+        );
+        assert_eq!(secrets.get(ProviderName::WeatherApi), None);
+    }
+}
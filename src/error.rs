@@ -0,0 +1,142 @@
+//! Module with error categories, which might be produced while retrieving weather data.
+
+/// Enumeration of error categories, which are produced by providers and the prompt agent.
+/// Library users can branch on the variants instead of matching error messages.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Configuration file couldn't be read or updated.
+    #[error("Configuration error: {0}")]
+    Config(String),
+    /// API key is missing, invalid, disabled or has no access to the requested resource.
+    #[error("Invalid credentials: {0}")]
+    Credentials(String),
+    /// User input can't be processed.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    /// Provider couldn't find the requested location.
+    #[error("Location not found: {0}")]
+    LocationNotFound(String),
+    /// Requested date is out of range, supported by the provider.
+    #[error("Date is out of provider's range: {0}")]
+    DateOutOfRange(String),
+    /// Provider couldn't be reached, or the request timed out.
+    #[error("Network error: {0}")]
+    Network(String),
+    /// Provider's request quota is exhausted.
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+    /// Provider failed to process the request or returned unexpected data.
+    #[error("Upstream error: {0}")]
+    Upstream(String),
+    /// Provider doesn't support the requested capability, e.g. reverse geocoding.
+    #[error("Unsupported by provider: {0}")]
+    Unsupported(String),
+}
+
+/// Result type with the crate's `Error`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Returns process exit code, which corresponds to the error category.
+    ///
+    /// | Code | Category                       |
+    /// |------|--------------------------------|
+    /// | 2    | Invalid command line usage     |
+    /// | 3    | Configuration                  |
+    /// | 4    | Credentials                    |
+    /// | 5    | Invalid input                  |
+    /// | 6    | Location not found             |
+    /// | 7    | Date out of provider's range   |
+    /// | 8    | Network                        |
+    /// | 9    | Upstream error                 |
+    /// | 10   | Quota exceeded                 |
+    /// | 11   | Unsupported by provider        |
+    ///
+    /// Code 2 is produced by the command line parser itself, before any error of this type might occur.
+    ///
+    /// # Examples
+    /// ```
+    /// use elastio_task::error::Error;
+    ///
+    /// let error = Error::LocationNotFound(String::from("SO INVALID ADDRESS"));
+    /// assert_eq!(error.exit_code(), 6);
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Config(_) => 3,
+            Error::Credentials(_) => 4,
+            Error::InvalidInput(_) => 5,
+            Error::LocationNotFound(_) => 6,
+            Error::DateOutOfRange(_) => 7,
+            Error::Network(_) => 8,
+            Error::Upstream(_) => 9,
+            Error::QuotaExceeded(_) => 10,
+            Error::Unsupported(_) => 11,
+        }
+    }
+
+    /// Returns whether another provider might succeed with the same request, e.g. when the provider
+    /// is unreachable, exhausted its quota, or doesn't support the requested date or capability.
+    ///
+    /// # Examples
+    /// ```
+    /// use elastio_task::error::Error;
+    ///
+    /// assert!(Error::Network(String::from("request timed out")).is_retryable());
+    /// assert!(!Error::LocationNotFound(String::from("SO INVALID ADDRESS")).is_retryable());
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Error::DateOutOfRange(_)
+                | Error::Network(_)
+                | Error::QuotaExceeded(_)
+                | Error::Upstream(_)
+                | Error::Unsupported(_)
+        )
+    }
+
+    /// Maps HTTP status code of a failed provider response into the error category.
+    /// Providers refine this mapping with the error codes from their response bodies.
+    ///
+    /// # Examples
+    /// ```
+    /// use elastio_task::error::Error;
+    ///
+    /// let error = Error::from_status(429, String::from("Too many requests"));
+    /// assert!(matches!(error, Error::QuotaExceeded(_)));
+    /// ```
+    pub fn from_status(status: u16, message: String) -> Error {
+        match status {
+            400 => Error::InvalidInput(message),
+            401 | 403 => Error::Credentials(message),
+            404 => Error::LocationNotFound(message),
+            429 => Error::QuotaExceeded(message),
+            _ => Error::Upstream(format!("HTTP {}: {}", status, message)),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Error::Network(format!("request timed out: {}", err))
+        } else if err.is_decode() {
+            Error::Upstream(format!("failed to decode response: {}", err))
+        } else {
+            Error::Network(err.to_string())
+        }
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Self {
+        Error::Config(format!("invalid provider URL: {}", err))
+    }
+}
+
+impl From<confy::ConfyError> for Error {
+    fn from(err: confy::ConfyError) -> Self {
+        Error::Config(err.to_string())
+    }
+}
@@ -1,3 +1,12 @@
 //! CLI application for weather data requests. Configurable, scalable, simple.
+pub mod cache;
+pub mod comparison;
+pub(crate) mod config;
+pub mod error;
+#[cfg(test)]
+mod mock_server;
+pub mod moment;
+pub mod output;
 pub mod prompt_agent;
 pub mod provider;
+pub mod units;
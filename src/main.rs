@@ -10,12 +10,12 @@ fn main() {
                 "Error: Command crashed during initialization steps: {}. Contact developers for proceeding.",
                 err
             );
-            std::process::exit(1);
+            std::process::exit(err.exit_code());
         }
     };
 
     if let Err(err) = agent.parse_command() {
         eprintln!("Error: {}.", err);
-        std::process::exit(1);
+        std::process::exit(err.exit_code());
     }
 }
@@ -0,0 +1,201 @@
+//! Local HTTP stand-in for provider APIs, which serves recorded fixtures to the tests.
+
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tiny_http::{Header, Response, Server};
+use url::Url;
+
+/// Header fields along with their values.
+type Headers = Vec<(String, String)>;
+
+/// Response, which is served for the requests to the matching path.
+#[derive(Debug, Clone)]
+struct Route {
+    path: String,
+    query: Option<String>,
+    status: u16,
+    body: String,
+    headers: Headers,
+}
+
+/// Local HTTP server, which responds with the registered routes and records every requested URL along with its headers.
+/// Runs on a random port in a background thread, which is stopped on drop.
+pub(crate) struct MockServer {
+    server: Arc<Server>,
+    routes: Arc<Mutex<Vec<Route>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+    request_headers: Arc<Mutex<Vec<Headers>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start() -> MockServer {
+        let server =
+            Arc::new(Server::http("127.0.0.1:0").expect("Unable to start local mock server"));
+        let routes = Arc::new(Mutex::new(Vec::<Route>::new()));
+        let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+        let request_headers = Arc::new(Mutex::new(Vec::<Headers>::new()));
+
+        let handle = {
+            let server = Arc::clone(&server);
+            let routes = Arc::clone(&routes);
+            let requests = Arc::clone(&requests);
+            let request_headers = Arc::clone(&request_headers);
+
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    let url = request.url().to_owned();
+                    requests.lock().unwrap().push(url.clone());
+                    request_headers.lock().unwrap().push(
+                        request
+                            .headers()
+                            .iter()
+                            .map(|header| (header.field.to_string(), header.value.to_string()))
+                            .collect(),
+                    );
+
+                    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+                    // The latest registered route wins, so tests might override the default ones.
+                    let route = routes
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .rev()
+                        .find(|route| {
+                            route.path == path
+                                && match route.query {
+                                    Some(ref route_query) => query.contains(route_query.as_str()),
+                                    None => true,
+                                }
+                        })
+                        .cloned();
+
+                    let (status, body, headers) = match route {
+                        Some(route) => (route.status, route.body, route.headers),
+                        None => (
+                            404,
+                            String::from(r#"{"message": "route is not mocked"}"#),
+                            Vec::new(),
+                        ),
+                    };
+
+                    let mut response = Response::from_string(body)
+                        .with_status_code(status)
+                        .with_header(
+                            Header::from_bytes("Content-Type", "application/json")
+                                .expect("Invalid mock server header"),
+                        );
+                    for (field, value) in headers {
+                        response.add_header(
+                            Header::from_bytes(field.as_bytes(), value.as_bytes())
+                                .expect("Invalid mock server header"),
+                        );
+                    }
+                    request.respond(response).ok();
+                }
+            })
+        };
+
+        MockServer {
+            server,
+            routes,
+            requests,
+            request_headers,
+            handle: Some(handle),
+        }
+    }
+
+    /// Base URL of the server, which might be injected into providers.
+    pub fn url(&self) -> Url {
+        let address = self
+            .server
+            .server_addr()
+            .to_ip()
+            .expect("Mock server should listen on IP address");
+        Url::parse(&format!("http://{}/", address)).expect("Invalid mock server URL")
+    }
+
+    /// Responds to every request to the 'path' with the 'status' and the 'body'.
+    pub fn route(&self, path: &str, status: u16, body: &str) -> &MockServer {
+        self.add_route(path, None, status, body, &[])
+    }
+
+    /// Responds to requests to the 'path', which query contains 'query', with the 'status' and the 'body'.
+    pub fn route_with_query(
+        &self,
+        path: &str,
+        query: &str,
+        status: u16,
+        body: &str,
+    ) -> &MockServer {
+        self.add_route(path, Some(query), status, body, &[])
+    }
+
+    /// Responds to every request to the 'path' with the 'status', the 'body' and additional 'headers'.
+    pub fn route_with_headers(
+        &self,
+        path: &str,
+        status: u16,
+        body: &str,
+        headers: &[(&str, &str)],
+    ) -> &MockServer {
+        self.add_route(path, None, status, body, headers)
+    }
+
+    /// URLs of all requests, which were received by the server.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Value of the 'field' header of the request with the 'index', compared case-insensitively.
+    pub fn request_header(&self, index: usize, field: &str) -> Option<String> {
+        self.request_headers
+            .lock()
+            .unwrap()
+            .get(index)?
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            .map(|(_, value)| value.clone())
+    }
+
+    fn add_route(
+        &self,
+        path: &str,
+        query: Option<&str>,
+        status: u16,
+        body: &str,
+        headers: &[(&str, &str)],
+    ) -> &MockServer {
+        self.routes.lock().unwrap().push(Route {
+            path: path.to_owned(),
+            query: query.map(str::to_owned),
+            status,
+            body: body.to_owned(),
+            headers: headers
+                .iter()
+                .map(|(field, value)| ((*field).to_owned(), (*value).to_owned()))
+                .collect(),
+        });
+        self
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Reads recorded provider's response from the 'tests/fixtures' folder, e.g. 'weather_api/current.json'.
+pub(crate) fn fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("Unable to read fixture {}: {}", path.display(), err))
+}
@@ -0,0 +1,363 @@
+//! Module for parsing dates and times, entered by the user, and resolving them into instants.
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::error::{Error, Result};
+
+static MOMENT_FORMATS_HINT: &str =
+    "expected YYYY-MM-DD, relative date (today, tomorrow, next friday, in 3 days, 2 days ago, +3d), \
+    RFC 3339 date-time (2023-04-07T18:00+02:00), local date-time (2023-04-07T18:00) or time (18:00)";
+
+/// Date or time, entered by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moment {
+    /// Whole day, e.g. '2023-04-07'.
+    Date(NaiveDate),
+    /// Whole day, relative to today in the location or the '--tz' timezone, e.g. 'tomorrow'.
+    Relative(RelativeDate),
+    /// Date-time without offset, e.g. '2023-04-07T18:00', which is local to the location or the '--tz' timezone.
+    LocalDateTime(NaiveDateTime),
+    /// Date-time with offset, e.g. '2023-04-07T18:00+02:00', which defines the instant by itself.
+    Instant(DateTime<Utc>),
+    /// Time of today, e.g. '18:00', which is local to the location or the '--tz' timezone.
+    Time(NaiveTime),
+}
+
+impl FromStr for Moment {
+    type Err = Error;
+
+    /// Parses moment. Date and time might be separated either with 'T' or with a space.
+    ///
+    /// # Examples
+    /// ```
+    /// use elastio_task::moment::Moment;
+    ///
+    /// assert!(matches!("2023-04-07".parse(), Ok(Moment::Date(_))));
+    /// assert!(matches!("next friday".parse(), Ok(Moment::Relative(_))));
+    /// assert!(matches!("2023-04-07 18:00".parse(), Ok(Moment::LocalDateTime(_))));
+    /// assert!(matches!("2023-04-07T18:00+02:00".parse(), Ok(Moment::Instant(_))));
+    /// assert!(matches!("18:00".parse(), Ok(Moment::Time(_))));
+    /// ```
+    fn from_str(input: &str) -> Result<Self> {
+        let input = input.trim();
+
+        if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+            return Ok(Moment::Date(date));
+        }
+        if let Some(relative) = RelativeDate::parse(input) {
+            return Ok(Moment::Relative(relative));
+        }
+        if let Ok(time) = parse_time(input) {
+            return Ok(Moment::Time(time));
+        }
+
+        let normalized = input.replacen(' ', "T", 1);
+        if let Ok(instant) = DateTime::parse_from_rfc3339(&normalized) {
+            return Ok(Moment::Instant(instant.with_timezone(&Utc)));
+        }
+        // RFC 3339 requires seconds, while users rarely type them.
+        if let Ok(instant) = DateTime::parse_from_str(&normalized, "%Y-%m-%dT%H:%M%:z") {
+            return Ok(Moment::Instant(instant.with_timezone(&Utc)));
+        }
+        if let Ok(instant) = NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%dT%H:%MZ") {
+            return Ok(Moment::Instant(Utc.from_utc_datetime(&instant)));
+        }
+        for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+            if let Ok(date_time) = NaiveDateTime::parse_from_str(&normalized, format) {
+                return Ok(Moment::LocalDateTime(date_time));
+            }
+        }
+
+        Err(Error::InvalidInput(format!(
+            "Unable to parse '{}': {}",
+            input, MOMENT_FORMATS_HINT
+        )))
+    }
+}
+
+impl Moment {
+    /// Resolves moment into the instant, interpreting local date-time or time in the 'timezone'.
+    /// Returns 'None' for the whole day, either absolute or relative.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if local time doesn't exist in the 'timezone', e.g. it's skipped by the DST transition.
+    pub fn resolve(&self, timezone: Tz, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+        let local = match *self {
+            Moment::Date(_) | Moment::Relative(_) => return Ok(None),
+            Moment::Instant(instant) => return Ok(Some(instant)),
+            Moment::LocalDateTime(date_time) => date_time,
+            Moment::Time(time) => now.with_timezone(&timezone).date_naive().and_time(time),
+        };
+
+        timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|instant| Some(instant.with_timezone(&Utc)))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("{} doesn't exist in {} timezone", local, timezone))
+            })
+    }
+
+    /// Replaces relative date with the absolute one, counted from 'today'. Other moments are returned as is.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if the date is out of the supported range.
+    pub fn resolve_date(self, today: NaiveDate) -> Result<Moment> {
+        match self {
+            Moment::Relative(relative) => relative.date(today).map(Moment::Date),
+            moment => Ok(moment),
+        }
+    }
+
+    /// Returns whether the moment is a whole day, either absolute or relative.
+    pub fn is_date(&self) -> bool {
+        matches!(self, Moment::Date(_) | Moment::Relative(_))
+    }
+
+    /// Returns whether the moment depends on the timezone, which it's interpreted in.
+    pub fn is_local(&self) -> bool {
+        matches!(
+            self,
+            Moment::Relative(_) | Moment::LocalDateTime(_) | Moment::Time(_)
+        )
+    }
+}
+
+/// Date, relative to today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeDate {
+    /// Number of days after today, negative for the days before, e.g. 'tomorrow', 'in 3 days', '2 days ago' or '+3d'.
+    Days(i64),
+    /// The nearest weekday, today included, e.g. 'friday'.
+    Weekday(Weekday),
+    /// The weekday after today, e.g. 'next friday'.
+    NextWeekday(Weekday),
+    /// The weekday before today, e.g. 'last friday'.
+    LastWeekday(Weekday),
+}
+
+impl RelativeDate {
+    /// Parses case-insensitive relative date, or returns 'None', if 'input' isn't one.
+    fn parse(input: &str) -> Option<RelativeDate> {
+        let input = input.to_lowercase();
+        let words: Vec<&str> = input.split_whitespace().collect();
+
+        let relative = match words.as_slice() {
+            ["today"] => RelativeDate::Days(0),
+            ["tomorrow"] => RelativeDate::Days(1),
+            ["yesterday"] => RelativeDate::Days(-1),
+            ["in", days, "day" | "days"] => RelativeDate::Days(days.parse::<u32>().ok()?.into()),
+            [days, "day" | "days", "ago"] => {
+                RelativeDate::Days(-i64::from(days.parse::<u32>().ok()?))
+            }
+            ["next", weekday] => RelativeDate::NextWeekday(weekday.parse().ok()?),
+            ["last", weekday] => RelativeDate::LastWeekday(weekday.parse().ok()?),
+            [word] if word.starts_with(['+', '-']) && word.ends_with('d') => {
+                let days = word[1..word.len() - 1].parse::<u32>().ok()?;
+                match word.starts_with('+') {
+                    true => RelativeDate::Days(days.into()),
+                    false => RelativeDate::Days(-i64::from(days)),
+                }
+            }
+            [weekday] => RelativeDate::Weekday(weekday.parse().ok()?),
+            _ => return None,
+        };
+
+        Some(relative)
+    }
+
+    /// Counts the date from 'today'.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if the date is out of the supported range.
+    pub fn date(&self, today: NaiveDate) -> Result<NaiveDate> {
+        let days_until = |weekday: Weekday| {
+            i64::from(weekday.num_days_from_monday())
+                - i64::from(today.weekday().num_days_from_monday())
+        };
+        let days = match *self {
+            RelativeDate::Days(days) => days,
+            RelativeDate::Weekday(weekday) => days_until(weekday).rem_euclid(7),
+            RelativeDate::NextWeekday(weekday) => (days_until(weekday) - 1).rem_euclid(7) + 1,
+            RelativeDate::LastWeekday(weekday) => -(-days_until(weekday) - 1).rem_euclid(7) - 1,
+        };
+
+        today
+            .checked_add_signed(Duration::days(days))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("Date {} days from {} is out of range", days, today))
+            })
+    }
+}
+
+/// Parses time in the HH:MM or HH:MM:SS format.
+pub fn parse_time(input: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+        .map_err(|_| {
+            Error::InvalidInput(format!("Unable to parse time '{}': expected HH:MM", input))
+        })
+}
+
+/// Parses IANA timezone identifier, e.g. 'Europe/Kyiv'.
+///
+/// # Errors:
+/// Returns 'InvalidInput' error for unknown timezone.
+pub fn parse_timezone(input: &str) -> Result<Tz> {
+    input.parse::<Tz>().map_err(|_| {
+        Error::InvalidInput(format!(
+            "Unknown timezone '{}': expected IANA identifier, e.g. Europe/Kyiv",
+            input
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instant(input: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(input)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_moment() {
+        let expected = instant("2023-04-07T16:00:00Z");
+        for input in [
+            "2023-04-07T18:00+02:00",
+            "2023-04-07 18:00+02:00",
+            "2023-04-07T18:00:00+02:00",
+            "2023-04-07T16:00Z",
+            "2023-04-07T16:00:00Z",
+        ] {
+            assert_eq!(input.parse::<Moment>().unwrap(), Moment::Instant(expected));
+        }
+
+        let local = NaiveDate::from_ymd_opt(2023, 4, 7)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap();
+        assert_eq!(
+            "2023-04-07T18:00".parse::<Moment>().unwrap(),
+            Moment::LocalDateTime(local)
+        );
+        assert_eq!(
+            "2023-04-07 18:00:00".parse::<Moment>().unwrap(),
+            Moment::LocalDateTime(local)
+        );
+
+        assert!(matches!(
+            "2000-12-32".parse::<Moment>(),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            "tomorrow evening".parse::<Moment>(),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_relative_date() {
+        for (input, expected) in [
+            ("today", RelativeDate::Days(0)),
+            ("Tomorrow", RelativeDate::Days(1)),
+            ("yesterday", RelativeDate::Days(-1)),
+            ("in 3 days", RelativeDate::Days(3)),
+            ("in 1 day", RelativeDate::Days(1)),
+            ("2 days ago", RelativeDate::Days(-2)),
+            ("+3d", RelativeDate::Days(3)),
+            ("-10d", RelativeDate::Days(-10)),
+            ("friday", RelativeDate::Weekday(Weekday::Fri)),
+            ("next Fri", RelativeDate::NextWeekday(Weekday::Fri)),
+            ("last monday", RelativeDate::LastWeekday(Weekday::Mon)),
+        ] {
+            assert_eq!(
+                input.parse::<Moment>().unwrap(),
+                Moment::Relative(expected),
+                "{}",
+                input
+            );
+        }
+
+        for input in [
+            "in -3 days",
+            "3d",
+            "+d",
+            "next",
+            "next week",
+            "in three days",
+        ] {
+            assert!(input.parse::<Moment>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_relative_date() {
+        // Friday.
+        let today = NaiveDate::from_ymd_opt(2023, 4, 7).unwrap();
+        let date = |relative: RelativeDate| relative.date(today).unwrap().to_string();
+
+        assert_eq!(date(RelativeDate::Days(-7)), "2023-03-31");
+        assert_eq!(date(RelativeDate::Weekday(Weekday::Fri)), "2023-04-07");
+        assert_eq!(date(RelativeDate::Weekday(Weekday::Mon)), "2023-04-10");
+        assert_eq!(date(RelativeDate::NextWeekday(Weekday::Fri)), "2023-04-14");
+        assert_eq!(date(RelativeDate::NextWeekday(Weekday::Sat)), "2023-04-08");
+        assert_eq!(date(RelativeDate::LastWeekday(Weekday::Fri)), "2023-03-31");
+        assert_eq!(date(RelativeDate::LastWeekday(Weekday::Thu)), "2023-04-06");
+        assert_eq!(date(RelativeDate::LastWeekday(Weekday::Sat)), "2023-04-01");
+
+        let moment = Moment::Relative(RelativeDate::Days(1));
+        assert_eq!(
+            moment.resolve_date(today).unwrap(),
+            Moment::Date(NaiveDate::from_ymd_opt(2023, 4, 8).unwrap())
+        );
+        assert!(matches!(
+            RelativeDate::Days(i64::from(u32::MAX)).date(today),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_moment() {
+        let timezone = parse_timezone("Europe/Kyiv").unwrap();
+        let now = instant("2023-04-06T22:30:00Z");
+
+        let moment: Moment = "2023-04-07T18:00".parse().unwrap();
+        assert_eq!(
+            moment.resolve(timezone, now).unwrap(),
+            Some(instant("2023-04-07T15:00:00Z"))
+        );
+
+        // It's already April 7 in Kyiv.
+        let moment: Moment = "09:00".parse().unwrap();
+        assert_eq!(
+            moment.resolve(timezone, now).unwrap(),
+            Some(instant("2023-04-07T06:00:00Z"))
+        );
+
+        let moment: Moment = "2023-03-26T03:30".parse().unwrap();
+        assert!(matches!(
+            moment.resolve(timezone, now),
+            Err(Error::InvalidInput(_))
+        ));
+
+        let moment: Moment = "2023-04-07".parse().unwrap();
+        assert_eq!(moment.resolve(timezone, now).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        assert!(parse_timezone("America/New_York").is_ok());
+        assert!(parse_timezone("UTC").is_ok());
+        assert!(matches!(
+            parse_timezone("Mars/Olympus"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+}
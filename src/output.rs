@@ -0,0 +1,493 @@
+//! Module for rendering weather reports in human and machine readable formats.
+use chrono::{DateTime, Utc};
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{Error, Result};
+use crate::provider::{ConditionKind, Place, ProviderName, Weather};
+use crate::units::{Quantity, Units};
+
+/// Format of the weather output, selected with the global '--format' option.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human readable table, preceded by a banner.
+    #[default]
+    Table,
+    /// JSON array of records, without banner.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+    /// Comma separated values with a header.
+    Csv,
+    /// YAML sequence of records.
+    Yaml,
+}
+
+/// Flat weather record, which schema is shared by all providers and all machine readable formats.
+/// Fields, which provider doesn't report, are empty, but never omitted. Values are in the 'units' system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherRecord {
+    pub provider: String,
+    pub units: Units,
+    pub location: String,
+    pub region: Option<String>,
+    pub country: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: Option<String>,
+    pub observed_at: DateTime<Utc>,
+    pub temperature: f64,
+    pub feels_like: Option<f64>,
+    pub humidity: Option<u8>,
+    pub pressure: Option<f64>,
+    pub precipitation: Option<f64>,
+    pub visibility: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub wind_direction: Option<u16>,
+    pub condition: ConditionKind,
+    pub description: String,
+}
+
+impl WeatherRecord {
+    /// Creates record from the provider's report, converting its values into the 'units' system.
+    pub fn new(provider_name: ProviderName, weather: &Weather, units: Units) -> WeatherRecord {
+        let convert =
+            |quantity, value: Option<f64>| value.map(|value| units.convert(quantity, value));
+
+        WeatherRecord {
+            provider: provider_name.get_pretty_name(),
+            units,
+            location: weather.location.name.clone(),
+            region: weather.location.region.clone(),
+            country: weather.location.country.clone(),
+            latitude: weather.location.latitude,
+            longitude: weather.location.longitude,
+            timezone: weather.location.timezone.clone(),
+            observed_at: weather.observed_at,
+            temperature: units.convert(Quantity::Temperature, weather.temperature),
+            feels_like: convert(Quantity::Temperature, weather.feels_like),
+            humidity: weather.humidity,
+            pressure: convert(Quantity::Pressure, weather.pressure),
+            precipitation: convert(Quantity::Precipitation, weather.precipitation),
+            visibility: convert(Quantity::Visibility, weather.visibility),
+            wind_speed: convert(Quantity::Speed, weather.wind.speed),
+            wind_direction: weather.wind.direction,
+            condition: weather.condition.kind,
+            description: weather.condition.description.clone(),
+        }
+    }
+
+    /// Place, which the record belongs to, as resolved by the provider.
+    pub fn place(&self) -> Place {
+        Place {
+            name: self.location.clone(),
+            region: self.region.clone(),
+            country: self.country.clone(),
+            latitude: self.latitude,
+            longitude: self.longitude,
+            timezone: self.timezone.clone(),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Renders records in the format.
+    ///
+    /// # Errors:
+    /// Returns 'Upstream' error, if provider's data can't be serialized.
+    pub fn render(&self, records: &[WeatherRecord]) -> Result<String> {
+        match self {
+            OutputFormat::Table => match records {
+                [record] => Ok(render_table(record)),
+                _ => Ok(render_series_table(records)),
+            },
+            _ => self.serialize(records),
+        }
+    }
+
+    /// Renders geocoding candidates in the format. Table is a numbered list.
+    ///
+    /// # Errors:
+    /// Returns 'Upstream' error, if provider's data can't be serialized.
+    pub fn render_places(&self, places: &[Place]) -> Result<String> {
+        match self {
+            OutputFormat::Table => Ok(places
+                .iter()
+                .enumerate()
+                .map(|(index, place)| {
+                    format!(
+                        "{}. {}, {}",
+                        index + 1,
+                        place,
+                        place.timezone.as_deref().unwrap_or("unknown timezone")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")),
+            _ => self.serialize(places),
+        }
+    }
+
+    /// Serializes records in the machine readable format. Tables depend on the records, so they're rendered separately.
+    fn serialize<T: Serialize + DeserializeOwned>(&self, records: &[T]) -> Result<String> {
+        match self {
+            OutputFormat::Table | OutputFormat::Json => {
+                serde_json::to_string_pretty(records).map_err(serialization_error)
+            }
+            OutputFormat::Ndjson => records
+                .iter()
+                .map(|record| serde_json::to_string(record).map_err(serialization_error))
+                .collect::<Result<Vec<_>>>()
+                .map(|lines| lines.join("\n")),
+            OutputFormat::Csv => {
+                // Header is written even without records, so the output is still a valid table.
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(Vec::new());
+                writer
+                    .write_record(field_names::<T>())
+                    .map_err(serialization_error)?;
+                for record in records {
+                    writer.serialize(record).map_err(serialization_error)?;
+                }
+                let bytes = writer
+                    .into_inner()
+                    .map_err(|err| serialization_error(err.error()))?;
+                String::from_utf8(bytes)
+                    .map(|csv| csv.trim_end().to_owned())
+                    .map_err(serialization_error)
+            }
+            OutputFormat::Yaml => serde_yaml::to_string(records)
+                .map(|yaml| yaml.trim_end().to_owned())
+                .map_err(serialization_error),
+        }
+    }
+}
+
+/// Names of the fields of the 'T' structure, as serde reports them to the deserializer.
+fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    T::deserialize(FieldNames(&mut fields)).ok();
+    fields
+}
+
+/// Deserializer, which only records the field names of the structure and fails.
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        Err(de::Error::custom("only structures are inspected"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("only field names are inspected"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+fn render_table(record: &WeatherRecord) -> String {
+    let label = |quantity| record.units.label(quantity);
+
+    let wind = match (record.wind_speed, record.wind_direction) {
+        (Some(speed), Some(direction)) => {
+            format!("{:.1} {}, {}°", speed, label(Quantity::Speed), direction)
+        }
+        (Some(speed), None) => format!("{:.1} {}", speed, label(Quantity::Speed)),
+        (None, Some(direction)) => format!("{}°", direction),
+        (None, None) => String::from("-"),
+    };
+
+    let rows = [
+        ("location", record.place().to_string()),
+        (
+            "timezone",
+            record.timezone.clone().unwrap_or_else(|| String::from("-")),
+        ),
+        (
+            "observed at",
+            record.observed_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        ),
+        (
+            "temperature",
+            format!("{:.1} {}", record.temperature, label(Quantity::Temperature)),
+        ),
+        (
+            "feels like",
+            optional(record.feels_like, label(Quantity::Temperature)),
+        ),
+        ("humidity", optional(record.humidity, "%")),
+        (
+            "pressure",
+            optional(record.pressure, label(Quantity::Pressure)),
+        ),
+        (
+            "precipitation",
+            optional(record.precipitation, label(Quantity::Precipitation)),
+        ),
+        (
+            "visibility",
+            optional(record.visibility, label(Quantity::Visibility)),
+        ),
+        ("wind", wind),
+        (
+            "condition",
+            format!("{} ({})", record.condition, record.description),
+        ),
+    ];
+
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or_default();
+    rows.iter()
+        .map(|(label, value)| format!("{:width$}  {}", label, value, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders several records as a table with a row per record, preceded by the place of the first record.
+fn render_series_table(records: &[WeatherRecord]) -> String {
+    let Some(first) = records.first() else {
+        return String::new();
+    };
+    let label = |quantity| first.units.label(quantity);
+
+    let header = vec![
+        String::from("observed at"),
+        format!("temperature, {}", label(Quantity::Temperature)),
+        format!("feels like, {}", label(Quantity::Temperature)),
+        String::from("humidity, %"),
+        format!("pressure, {}", label(Quantity::Pressure)),
+        format!("precipitation, {}", label(Quantity::Precipitation)),
+        format!("wind, {}", label(Quantity::Speed)),
+        String::from("condition"),
+    ];
+    let mut lines = vec![header];
+    for record in records {
+        lines.push(vec![
+            record.observed_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            format!("{:.1}", record.temperature),
+            optional(record.feels_like, ""),
+            optional(record.humidity, ""),
+            optional(record.pressure, ""),
+            optional(record.precipitation, ""),
+            optional(record.wind_speed, ""),
+            record.condition.to_string(),
+        ]);
+    }
+
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|column| {
+            lines
+                .iter()
+                .map(|line| line[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let rows = lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("location: {}\n{}", first.place(), rows)
+}
+
+fn optional<T: std::fmt::Display>(value: Option<T>, unit: &str) -> String {
+    match value {
+        Some(value) => format!("{} {}", value, unit).trim_end().to_owned(),
+        None => String::from("-"),
+    }
+}
+
+fn serialization_error(err: impl std::fmt::Display) -> Error {
+    Error::Upstream(format!("Failed to serialize weather data: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn record() -> WeatherRecord {
+        WeatherRecord {
+            provider: String::from("weather-api"),
+            units: Units::Metric,
+            location: String::from("Mykolaiv"),
+            region: Some(String::from("Lviv oblast")),
+            country: Some(String::from("Ukraine")),
+            latitude: 49.52,
+            longitude: 23.98,
+            timezone: Some(String::from("Europe/Kyiv")),
+            observed_at: Utc.with_ymd_and_hms(2023, 4, 7, 12, 0, 0).unwrap(),
+            temperature: 12.5,
+            feels_like: None,
+            humidity: Some(70),
+            pressure: Some(1015.0),
+            precipitation: Some(0.3),
+            visibility: None,
+            wind_speed: Some(3.0),
+            wind_direction: Some(240),
+            condition: ConditionKind::Rain,
+            description: String::from("Light rain"),
+        }
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = OutputFormat::Json.render(&[record()]).unwrap();
+        let records: Vec<WeatherRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records, vec![record()]);
+        assert!(json.contains(r#""feels_like": null"#));
+    }
+
+    #[test]
+    fn test_render_ndjson() {
+        let ndjson = OutputFormat::Ndjson.render(&[record(), record()]).unwrap();
+        assert_eq!(ndjson.lines().count(), 2);
+        for line in ndjson.lines() {
+            assert_eq!(
+                serde_json::from_str::<WeatherRecord>(line).unwrap(),
+                record()
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let csv = OutputFormat::Csv.render(&[record()]).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("provider,units,location,region,country,latitude,longitude,timezone,observed_at,temperature,feels_like,humidity,pressure,precipitation,visibility,wind_speed,wind_direction,condition,description")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("weather-api,metric,Mykolaiv,Lviv oblast,Ukraine,49.52,23.98,Europe/Kyiv,2023-04-07T12:00:00Z,12.5,,70,1015.0,0.3,,3.0,240,rain,Light rain")
+        );
+    }
+
+    #[test]
+    fn test_render_csv_empty() {
+        let csv = OutputFormat::Csv.render(&[]).unwrap();
+        assert_eq!(csv.lines().count(), 1);
+        assert!(csv.starts_with("provider,units,location,"));
+
+        let csv = OutputFormat::Csv.render_places(&[]).unwrap();
+        assert_eq!(csv, "name,region,country,latitude,longitude,timezone");
+    }
+
+    #[test]
+    fn test_render_yaml() {
+        let yaml = OutputFormat::Yaml.render(&[record()]).unwrap();
+        let records: Vec<WeatherRecord> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(records, vec![record()]);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = OutputFormat::Table.render(&[record()]).unwrap();
+        assert!(table.contains("location       Mykolaiv, Lviv oblast, Ukraine (49.52, 23.98)\n"));
+        assert!(table.contains("feels like     -\n"));
+        assert!(table.contains("precipitation  0.3 mm\n"));
+        assert!(table.contains("wind           3.0 m/s, 240°\n"));
+    }
+
+    #[test]
+    fn test_render_series_table() {
+        let mut second = record();
+        second.observed_at = Utc.with_ymd_and_hms(2023, 4, 8, 12, 0, 0).unwrap();
+        second.feels_like = Some(-1.5);
+        let table = OutputFormat::Table.render(&[record(), second]).unwrap();
+
+        let mut lines = table.lines();
+        assert_eq!(
+            lines.next(),
+            Some("location: Mykolaiv, Lviv oblast, Ukraine (49.52, 23.98)")
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("observed at           temperature, °C  feels like, °C"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("2023-04-07 12:00 UTC  12.5             -  "));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("2023-04-08 12:00 UTC  12.5             -1.5"));
+    }
+
+    #[test]
+    fn test_render_places() {
+        let mut places = vec![record().place(), record().place()];
+        places[1].timezone = None;
+
+        let list = OutputFormat::Table.render_places(&places).unwrap();
+        assert_eq!(
+            list,
+            "1. Mykolaiv, Lviv oblast, Ukraine (49.52, 23.98), Europe/Kyiv\n\
+            2. Mykolaiv, Lviv oblast, Ukraine (49.52, 23.98), unknown timezone"
+        );
+
+        let json = OutputFormat::Json.render_places(&places).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Place>>(&json).unwrap(), places);
+    }
+
+    #[test]
+    fn test_weather_record_units() {
+        let weather: Weather = serde_json::from_str(
+            r#"{
+                "location": {"name": "Lviv", "region": null, "country": "UA", "latitude": 49.84, "longitude": 24.03, "timezone": null},
+                "observed_at": "2023-04-07T12:00:00Z", "temperature": 10.0, "feels_like": null, "humidity": 70,
+                "pressure": 1016.0, "precipitation": 2.54, "visibility": 10.0,
+                "wind": {"speed": 4.47, "direction": 240},
+                "condition": {"kind": "rain", "description": "Light rain"}
+            }"#,
+        )
+        .unwrap();
+
+        let record = WeatherRecord::new(ProviderName::WeatherApi, &weather, Units::Imperial);
+        assert_eq!(record.units, Units::Imperial);
+        assert_eq!(record.temperature, 50.0);
+        assert_eq!(record.feels_like, None);
+        assert_eq!(record.pressure, Some(30.0));
+        assert_eq!(record.precipitation, Some(0.1));
+        assert_eq!(record.visibility, Some(6.21));
+        assert_eq!(record.wind_speed, Some(10.0));
+
+        let table = OutputFormat::Table.render(&[record]).unwrap();
+        assert!(table.contains("temperature    50.0 °F\n"));
+        assert!(table.contains("wind           10.0 mph, 240°\n"));
+    }
+}
@@ -1,16 +1,30 @@
 //! Module for managing user and provider's communication.
 
-use std::collections::HashMap;
+use std::cell::OnceCell;
+use std::io::IsTerminal;
 
-use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
 use clap::Parser;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 
-use crate::provider::{Provider, ProviderName};
+use crate::cache::Cache;
+use crate::comparison::Comparison;
+use crate::config::{self, ApiKeySource, ApplicationConfig, SecretsConfig};
+use crate::error::{Error, Result};
+use crate::moment::{parse_time, parse_timezone, Moment};
+use crate::output::{OutputFormat, WeatherRecord};
+use crate::provider::fallback::FallbackChain;
+use crate::provider::met_no::Product;
+use crate::provider::{Coordinates, Place, Provider, ProviderName, Weather};
+use crate::units::Units;
 
-static APP_NAME: &str = "ELASTIO_TASK";
+static TEST_ADDRESS: &str = "London, United Kingdom";
+/// Maximum number of days, which might be requested at once.
+static MAX_SERIES_DAYS: i64 = 31;
+/// Places, which are closer than this (in degrees of both latitude and longitude), are considered the same one.
+static SAME_PLACE_DEGREES: f64 = 0.1;
 
 #[derive(Parser, Debug)]
 #[command(about = "Forecasts and displays present and past weather.")]
@@ -18,6 +32,25 @@ static APP_NAME: &str = "ELASTIO_TASK";
 struct Application {
     #[command(subcommand)]
     command: InputSubcommand,
+    #[command(flatten)]
+    options: GlobalOptions,
+}
+
+/// Structure for mapping options, which are accepted by every subcommand.
+#[derive(clap::Args, Debug, Clone, Default)]
+struct GlobalOptions {
+    /// Performs provider's requests without reading or updating the response cache.
+    #[arg(long, global = true)]
+    no_cache: bool,
+    /// Format of the weather output.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    format: OutputFormat,
+    /// Unit system of the weather output. Overrides the default one, stored with 'units' subcommand.
+    #[arg(long, global = true, value_enum)]
+    units: Option<Units>,
+    /// Provider, which is used for this command only. Overrides the configured one, which stays stored.
+    #[arg(long, global = true, value_enum, env = "ELASTIO_TASK_PROVIDER")]
+    provider: Option<ProviderName>,
 }
 
 /// Enumeration for possible input subcommand variants.
@@ -30,153 +63,1295 @@ pub enum InputSubcommand {
     Configure(ProviderName),
     /// Gets apporpriate weather data, based on address and date(YYYY-MM-DD), if provided, and current weather, if not.
     /// Example: get "L'aquila, Italy" 2023-04-07
+    /// Example: get "L'aquila, Italy" --from 2023-04-01 --to 2023-04-07
+    /// Example: get "L'aquila, Italy" 2023-04-07 --hourly
     Get(SpaceTimeConfig),
-    /// Displays currently used provider.
+    /// Gets weather data from every provider, which has api key, and displays it side by side.
+    /// Example: compare "L'aquila, Italy" 2023-04-07
+    Compare(SpaceTimeConfig),
+    /// Displays all places, which match the query, with their region, country, coordinates and timezone,
+    /// if the geocoder reports it.
+    /// With '--reverse', displays places nearby the coordinates instead.
+    /// Example: locate Mykolaiv
+    /// Example: locate --reverse 49.84,24.03
+    Locate {
+        #[arg(required_unless_present = "reverse")]
+        query: Option<String>,
+        /// Coordinates(latitude,longitude, e.g. 49.84,24.03), which nearby places are displayed.
+        #[arg(
+            long,
+            conflicts_with = "query",
+            allow_hyphen_values = true,
+            value_name = "LAT,LON"
+        )]
+        reverse: Option<Coordinates>,
+    },
+    /// Configures ordered list of providers, which are tried when the current provider fails
+    /// or doesn't support the requested date. Without providers, disables the fallback.
+    /// Example: fallback weather-api
+    Fallback {
+        #[arg(value_enum)]
+        providers: Vec<ProviderName>,
+    },
+    /// Stores default unit system of the weather output, or displays it, if unit system isn't specified.
+    /// Example: units imperial
+    Units {
+        #[arg(value_enum)]
+        units: Option<Units>,
+    },
+    /// Stores default location(address, alias of the saved place or coordinates), which is used, when the address
+    /// isn't specified, or displays it, if location isn't specified.
+    /// Example: default-location office
+    DefaultLocation {
+        location: Option<String>,
+        /// Removes the default location.
+        #[arg(long, conflicts_with = "location")]
+        clear: bool,
+    },
+    /// Displays currently used provider and its fallback providers.
     CurrentProvider,
+    /// Manages provider's api keys, stored in the application's secrets file.
+    /// Environment variables, e.g. WEATHER_API, override stored keys.
+    #[clap(subcommand)]
+    Keys(KeysSubcommand),
+    /// Manages the cache of provider's responses.
+    #[clap(subcommand)]
+    Cache(CacheSubcommand),
+    /// Manages saved places, which aliases might be used instead of the address.
+    #[clap(subcommand)]
+    Places(PlacesSubcommand),
+}
+
+/// Enumeration for possible 'keys' subcommand variants.
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum KeysSubcommand {
+    /// Stores api key for the provider.
+    /// Example: keys set weather-api 0123456789abcdef
+    Set {
+        #[arg(value_enum)]
+        provider_name: ProviderName,
+        api_key: String,
+    },
+    /// Displays masked api keys of all providers and where they come from.
+    List,
+    /// Performs a test request to the provider with its api key.
+    /// Example: keys test open-weather-map
+    Test {
+        #[arg(value_enum)]
+        provider_name: ProviderName,
+    },
+    /// Removes stored api key of the provider.
+    /// Example: keys remove weather-api
+    Remove {
+        #[arg(value_enum)]
+        provider_name: ProviderName,
+    },
+}
+
+/// Enumeration for possible 'places' subcommand variants.
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum PlacesSubcommand {
+    /// Resolves the address or coordinates, and saves the place along with its timezone under the alias.
+    /// Example: places add office "Lviv, Ukraine"
+    Add {
+        alias: String,
+        address: String,
+        /// Number of the place(starting from 1) to save, when the address matches several places.
+        #[arg(long)]
+        pick: Option<usize>,
+        /// Country(as the provider reports it, e.g. UA or Ukraine), which the matched places are narrowed to.
+        #[arg(long)]
+        country: Option<String>,
+    },
+    /// Displays saved places with their aliases.
+    List,
+    /// Changes alias of the saved place.
+    /// Example: places rename office work
+    Rename { alias: String, new_alias: String },
+    /// Removes saved place.
+    /// Example: places remove office
+    Remove { alias: String },
+}
+
+/// Enumeration for possible 'cache' subcommand variants.
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum CacheSubcommand {
+    /// Displays number and size of cached responses per endpoint, along with their time to live.
+    Stats,
+    /// Removes all cached responses.
+    Clear,
 }
 
 /// Structure for mapping input configuration for 'get' subcommand.
 #[derive(clap::Args, Debug, Clone, Serialize, Deserialize)]
 pub struct SpaceTimeConfig {
-    pub address: String,
+    /// Address, alias of the saved place or coordinates(latitude,longitude, e.g. 49.84,24.03).
+    /// The default location is used, if it's omitted. A single positional argument, which is a date, is the date.
+    pub address: Option<String>,
+    /// Date(YYYY-MM-DD), relative date(today, tomorrow, next friday, in 3 days, 2 days ago, +3d), date-time with offset(2023-04-07T18:00+02:00), local date-time(2023-04-07T18:00) or time(18:00).
     pub date: Option<String>,
+    /// First date(YYYY-MM-DD) of the range, which weather is retrieved for every day.
+    #[arg(long, conflicts_with = "date", requires = "to")]
+    pub from: Option<String>,
+    /// Last date(YYYY-MM-DD) of the range, inclusively.
+    #[arg(long, requires = "from")]
+    pub to: Option<String>,
+    /// Time(HH:MM) of the date in the location's local time. Weather is retrieved for the hour of the time.
+    #[arg(long, conflicts_with_all = ["from", "hourly"])]
+    pub time: Option<String>,
+    /// Retrieves hour-by-hour weather for every hour of the date in the location's local time.
+    #[arg(long, conflicts_with = "from")]
+    pub hourly: bool,
+    /// IANA timezone(e.g. Europe/Kyiv), in which local date-times and times are interpreted instead of the location's one.
+    #[arg(long, conflicts_with = "from")]
+    pub tz: Option<String>,
+    /// Latitude of the location, which is used instead of the address. The first positional argument is the date then.
+    #[arg(long, requires = "lon", allow_negative_numbers = true)]
+    pub lat: Option<f64>,
+    /// Longitude of the location.
+    #[arg(long, requires = "lat", allow_negative_numbers = true)]
+    pub lon: Option<f64>,
+    /// Number of the place(starting from 1) to use, when the address matches several places.
+    #[arg(long, conflicts_with = "lat")]
+    pub pick: Option<usize>,
+    /// Country(as the provider reports it, e.g. UA or Ukraine), which the matched places are narrowed to.
+    #[arg(long, conflicts_with = "lat")]
+    pub country: Option<String>,
 }
 
-/// Structure for retrieving stored provider from configuration file.
-#[derive(Serialize, Deserialize, Debug, Default)]
-struct ApplicationConfig {
-    provider_name: ProviderName,
+impl SpaceTimeConfig {
+    /// Replaces '--lat' and '--lon' options with the address of the coordinates. In that case,
+    /// the only positional argument is the date. Otherwise, the 'default_location' is used, if the address
+    /// is omitted, or if the only positional argument is a date.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error for coordinates out of range, if both address and coordinates are specified,
+    /// if there is neither address, nor the default location, or if '--time' or '--hourly' is specified without date.
+    fn resolve_location(mut self, default_location: Option<&str>) -> Result<SpaceTimeConfig> {
+        if let (Some(latitude), Some(longitude)) = (self.lat.take(), self.lon.take()) {
+            if self.date.is_some() {
+                return Err(Error::InvalidInput(String::from(
+                    "Either address or '--lat' and '--lon' options should be specified, not both",
+                )));
+            }
+            self.date = self.address.take();
+            self.address = Some(Coordinates::new(latitude, longitude)?.to_string());
+            return self.check_date();
+        }
+
+        let is_date = |address: &String| address.parse::<Moment>().is_ok();
+        if self.date.is_none() && self.address.iter().all(is_date) {
+            if self.from.is_some() && self.address.is_some() {
+                return Err(Error::InvalidInput(String::from(
+                    "Either date or '--from' and '--to' options should be specified, not both",
+                )));
+            }
+            self.date = self.address.take();
+            self.address = Some(default_location.map(str::to_owned).ok_or_else(|| {
+                Error::InvalidInput(String::from(
+                    "Address isn't specified. Specify it, or store the default location with 'default-location <address>'",
+                ))
+            })?);
+        }
+
+        self.check_date()
+    }
+
+    /// Checks, that options, which apply to a date, are specified along with the date, and that the date, time,
+    /// timezone and range are valid, so invalid input is rejected before any provider's request.
+    fn check_date(self) -> Result<SpaceTimeConfig> {
+        if self.date.is_none() && (self.hourly || self.time.is_some()) {
+            return Err(Error::InvalidInput(String::from(
+                "'--hourly' and '--time' options require a date",
+            )));
+        }
+        if let Some(ref date) = self.date {
+            date.parse::<Moment>()?;
+        }
+        if let Some(ref time) = self.time {
+            parse_time(time)?;
+        }
+        if let Some(ref tz) = self.tz {
+            parse_timezone(tz)?;
+        }
+        if let (Some(from), Some(to)) = (&self.from, &self.to) {
+            parse_date_range(from, to)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Address of the resolved location.
+    fn address(&self) -> &str {
+        self.address
+            .as_deref()
+            .expect("Address is required, unless coordinates are specified")
+    }
 }
 
 /// Entity, which is responsible for managing provider's and users communication.
 pub struct PromptAgent {
-    current_provider: Box<dyn Provider>,
-    current_provider_name: ProviderName,
+    config: ApplicationConfig,
+    providers: OnceCell<FallbackChain>,
+    /// Whether the user might be asked to pick one of the places, which match the address.
+    interactive: bool,
+    /// Provider, which is used instead of the configured one for this command only.
+    provider_override: Option<ProviderName>,
 }
 
 impl PromptAgent {
-    /// Creates new entity of PromptAgent structure with stored\default provider as a trait object.
+    /// Creates new entity of PromptAgent structure with stored\default provider.
+    /// Provider's api_key is loaded lazily, only by the commands, which perform provider's requests.
     ///
     /// # Errors:
-    /// Backpropagates error in case of invalid .env file configuration.
-    pub fn new() -> anyhow::Result<Self> {
-        let config: Result<ApplicationConfig, confy::ConfyError> = confy::load(APP_NAME, None);
-
-        let provider_name = match config {
-            Ok(config) => config.provider_name,
-            Err(err) => return Err(anyhow::anyhow!("Failed to retrieve config: {}", err)),
-        };
-
-        let available_providers = Self::get_available_providers()?;
-        let provider_key = available_providers
-            .get(&provider_name)
-            .ok_or_else(|| anyhow::anyhow!("Couldn't retrieve required api_key"))?
-            .to_owned();
-
-        let provider: Box<dyn Provider> = provider_name.get_provider_instance(provider_key);
-
+    /// Backpropagates error in case of invalid configuration file.
+    pub fn new() -> Result<Self> {
         Ok(PromptAgent {
-            current_provider: provider,
-            current_provider_name: provider_name,
+            config: ApplicationConfig::load()?,
+            providers: OnceCell::new(),
+            interactive: std::io::stdin().is_terminal() && std::io::stderr().is_terminal(),
+            provider_override: None,
         })
     }
 
+    /// Creates new entity of PromptAgent structure with already instantiated provider, e.g. one backed by a mock server.
+    #[cfg(test)]
+    fn with_provider(provider_name: ProviderName, provider: Box<dyn Provider>) -> Self {
+        Self::with_providers(vec![(provider_name, provider)])
+    }
+
+    /// Creates new entity of PromptAgent structure with already instantiated chain of providers.
+    #[cfg(test)]
+    fn with_providers(providers: Vec<(ProviderName, Box<dyn Provider>)>) -> Self {
+        PromptAgent {
+            config: ApplicationConfig {
+                provider_name: providers[0].0,
+                fallback: providers.iter().skip(1).map(|(name, _)| *name).collect(),
+                ..ApplicationConfig::default()
+            },
+            providers: OnceCell::from(FallbackChain::new(providers)),
+            interactive: false,
+            provider_override: None,
+        }
+    }
+
     /// Performs CLI input parsing and prints the output to the console.
+    /// Provider, which is specified with '--provider' or ELASTIO_TASK_PROVIDER, is used for this command only.
     ///
     /// # Errors:
     /// Backpropagates errors in case of API modification\limitation or invalid input.
-    pub fn parse_command(&self) -> anyhow::Result<()> {
+    pub fn parse_command(self) -> Result<()> {
         let command = Application::parse();
-        self.process_command(command)
+        PromptAgent {
+            provider_override: command.options.provider,
+            ..self
+        }
+        .process_command(command)
     }
 
-    fn process_command(&self, command: Application) -> anyhow::Result<()> {
-        let date_time_regex = Regex::new(r"^\d{4}-\d{2}-\d{2}$")
-            .expect("Failed during regular expression initialization");
+    fn process_command(&self, command: Application) -> Result<()> {
+        let options = command.options;
+        let use_cache = !options.no_cache;
+        let units = options.units.unwrap_or(self.config.units);
         match command.command {
-            InputSubcommand::Get(space_time_config) => match space_time_config.date {
-                Some(ref date) if !date_time_regex.is_match(date) => Err(anyhow::anyhow!(
-                    "Entered date should be in the YYYY-MM-DD format"
-                )),
-                Some(ref date) => {
-                    let weather = self
-                        .current_provider
-                        .get_timed_weather(&space_time_config.address, date)?;
-
-                    println!(
-                        "-- Weather for {} on {}: \n{}",
-                        &space_time_config.address, date, weather
+            InputSubcommand::Get(space_time_config) => {
+                let (space_time_config, place) =
+                    self.disambiguate(self.resolve_location(space_time_config)?, use_cache)?;
+                let (Some(from), Some(to)) = (&space_time_config.from, &space_time_config.to)
+                else {
+                    return self.get(
+                        &space_time_config,
+                        place.as_ref(),
+                        use_cache,
+                        options.format,
+                        units,
                     );
+                };
 
-                    Ok(())
+                let address = space_time_config.address();
+                let (from, to) = parse_date_range(from, to)?;
+                let timezone = known_timezone(&space_time_config, place.as_ref())?;
+                let (provider_name, mut series) = self
+                    .get_providers(use_cache)?
+                    .get_weather_series(address, from, to, timezone)?;
+                let place = place.or_else(|| self.nearby_place(address, &series, use_cache));
+                name_locations(&mut series, place.as_ref());
+
+                let banner = format!(
+                    "-- Weather for {} from {} to {} by {}: ",
+                    describe_location(address, place.as_ref()),
+                    from,
+                    to,
+                    provider_name.get_pretty_name()
+                );
+                let records: Vec<WeatherRecord> = series
+                    .iter()
+                    .map(|weather| WeatherRecord::new(provider_name, weather, units))
+                    .collect();
+
+                print_weather(options.format, &banner, &records)
+            }
+            InputSubcommand::Compare(space_time_config) => {
+                let space_time_config = self.resolve_location(space_time_config)?;
+                if space_time_config.from.is_some() {
+                    return Err(Error::InvalidInput(String::from(
+                        "Date ranges aren't supported by 'compare', use a single date instead",
+                    )));
+                }
+                if space_time_config.hourly || space_time_config.time.is_some() {
+                    return Err(Error::InvalidInput(String::from(
+                        "Hourly weather isn't supported by 'compare', use a date instead",
+                    )));
+                }
+                if let Some(ref date) = space_time_config.date {
+                    if !date.parse::<Moment>()?.is_date() {
+                        return Err(Error::InvalidInput(String::from(
+                            "Date-times aren't supported by 'compare', use a date(YYYY-MM-DD) instead",
+                        )));
+                    }
                 }
-                None => {
-                    let weather = self
-                        .current_provider
-                        .get_current_weather(&space_time_config.address)?;
 
+                let (space_time_config, place) = self.disambiguate(space_time_config, use_cache)?;
+                self.compare(
+                    &space_time_config,
+                    place.as_ref(),
+                    use_cache,
+                    options.format,
+                    units,
+                )
+            }
+            InputSubcommand::Locate { query, reverse } => {
+                let providers = self.get_providers(use_cache)?;
+                let (query, (provider_name, places)) = match (reverse, query) {
+                    (Some(coordinates), _) => (
+                        format!("nearby {}", coordinates),
+                        providers.reverse_geocode(coordinates)?,
+                    ),
+                    (None, Some(query)) => {
+                        let places = providers.geocode(&query)?;
+                        (format!("matching '{}'", query), places)
+                    }
+                    (None, None) => {
+                        unreachable!("Query is required, unless coordinates are specified")
+                    }
+                };
+
+                if options.format == OutputFormat::Table {
                     println!(
-                        "-- Current weather for {}: \n{}",
-                        &space_time_config.address, weather
+                        "-- Places {} by {}: ",
+                        query,
+                        provider_name.get_pretty_name()
                     );
-
-                    Ok(())
                 }
-            },
+                println!("{}", options.format.render_places(&places)?);
+
+                Ok(())
+            }
             InputSubcommand::Configure(provider_name) => {
-                if provider_name == self.current_provider_name {
+                if provider_name == self.config.provider_name {
                     println!(
                         "-- Provider {} is already in use.",
-                        self.current_provider_name.get_pretty_name()
+                        self.config.provider_name.get_pretty_name()
                     );
                 } else {
                     println!(
                         "-- Changing provider: {} => {}.",
-                        self.current_provider_name.get_pretty_name(),
+                        self.config.provider_name.get_pretty_name(),
                         provider_name.get_pretty_name()
                     );
 
-                    match confy::store(APP_NAME, None, ApplicationConfig { provider_name }) {
-                        Ok(_) => {
-                            println!("-- Provider was successfully changed.");
-                        }
-                        Err(err) => {
-                            return Err(anyhow::anyhow!(
-                                "There was an issue while updating configuration file: {}",
-                                err
-                            ));
-                        }
+                    ApplicationConfig {
+                        provider_name,
+                        ..self.config.clone()
                     }
+                    .store()?;
+                    println!("-- Provider was successfully changed.");
+                }
+
+                Ok(())
+            }
+            InputSubcommand::Fallback { providers } => {
+                let config = ApplicationConfig {
+                    fallback: providers,
+                    ..self.config.clone()
+                };
+                config.store()?;
+
+                match config.fallback.is_empty() {
+                    true => println!("-- Fallback providers were disabled."),
+                    false => println!(
+                        "-- Providers are tried in order: {}.",
+                        pretty_names(&config.provider_chain())
+                    ),
+                }
+
+                Ok(())
+            }
+            InputSubcommand::Units { units: None } => {
+                println!("-- Default units: {}.", self.config.units);
+
+                Ok(())
+            }
+            InputSubcommand::Units { units: Some(units) } => {
+                ApplicationConfig {
+                    units,
+                    ..self.config.clone()
+                }
+                .store()?;
+                println!("-- Default units were changed to {}.", units);
+
+                Ok(())
+            }
+            InputSubcommand::DefaultLocation {
+                location: None,
+                clear: false,
+            } => {
+                match self.config.default_location {
+                    Some(ref location) => println!("-- Default location: {}.", location),
+                    None => println!("-- There is no default location."),
+                }
+
+                Ok(())
+            }
+            InputSubcommand::DefaultLocation { location, .. } => {
+                if let Some(ref location) = location {
+                    if location.parse::<Moment>().is_ok() {
+                        return Err(Error::InvalidInput(format!(
+                            "'{}' is a date, not a location",
+                            location
+                        )));
+                    }
+                }
+
+                ApplicationConfig {
+                    default_location: location.clone(),
+                    ..self.config.clone()
+                }
+                .store()?;
+                match location {
+                    Some(location) => println!("-- Default location was changed to {}.", location),
+                    None => println!("-- Default location was removed."),
                 }
 
                 Ok(())
             }
             InputSubcommand::CurrentProvider => {
+                match self.provider_override {
+                    Some(provider_name) if provider_name != self.config.provider_name => println!(
+                        "-- Current provider: {} (instead of the configured {} for this command only).",
+                        provider_name.get_pretty_name(),
+                        self.config.provider_name.get_pretty_name()
+                    ),
+                    _ => println!(
+                        "-- Current provider: {}.",
+                        self.config.provider_name.get_pretty_name()
+                    ),
+                }
+                let chain = self.provider_chain();
+                if chain.len() > 1 {
+                    println!("-- Fallback providers: {}.", pretty_names(&chain[1..]));
+                }
+
+                Ok(())
+            }
+            InputSubcommand::Keys(keys_subcommand) => Self::process_keys_command(keys_subcommand),
+            InputSubcommand::Cache(cache_subcommand) => {
+                self.process_cache_command(cache_subcommand)
+            }
+            InputSubcommand::Places(places_subcommand) => {
+                self.process_places_command(places_subcommand, use_cache)
+            }
+        }
+    }
+
+    /// Resolves location of the 'space_time_config', falling back to the default location.
+    ///
+    /// # Errors:
+    /// Backpropagates errors of 'SpaceTimeConfig::resolve_location'.
+    fn resolve_location(&self, space_time_config: SpaceTimeConfig) -> Result<SpaceTimeConfig> {
+        space_time_config.resolve_location(self.config.default_location.as_deref())
+    }
+
+    /// Replaces the address with coordinates of the place, which is saved under the address as alias, or
+    /// which the address is geocoded into, so that providers don't geocode it once again. The place is picked
+    /// with '--pick' or by the user, when the address matches several places. Saved places are resolved without
+    /// provider's requests. Returns the place along with the updated config, unless the address is coordinates.
+    ///
+    /// # Errors:
+    /// Backpropagates errors of 'pick_place'.
+    fn disambiguate(
+        &self,
+        mut space_time_config: SpaceTimeConfig,
+        use_cache: bool,
+    ) -> Result<(SpaceTimeConfig, Option<Place>)> {
+        let address = space_time_config.address();
+        if address.parse::<Coordinates>().is_ok() {
+            return Ok((space_time_config, None));
+        }
+
+        let place = match self.config.places.get(address) {
+            Some(place) => {
+                if space_time_config.tz.is_none() {
+                    space_time_config.tz = place.timezone.clone();
+                }
+                place.clone()
+            }
+            None => {
+                let pick = space_time_config.pick;
+                let country = space_time_config.country.as_deref();
+                self.pick_place(address, pick, country, use_cache)?.0
+            }
+        };
+
+        space_time_config.address = Some(place.coordinates().to_string());
+        Ok((space_time_config, Some(place)))
+    }
+
+    /// Geocodes the 'address' into the place, which is picked with 'pick' or by the user, when the address
+    /// matches several places. Places are narrowed to the 'country' ones beforehand.
+    /// Returns the place along with the number of places, which it was picked from.
+    ///
+    /// # Errors:
+    /// Returns 'LocationNotFound' error, if none of the places is in the country, and 'InvalidInput' error
+    /// for invalid 'pick', or if the address is ambiguous and the user can't be asked.
+    fn pick_place(
+        &self,
+        address: &str,
+        pick: Option<usize>,
+        country: Option<&str>,
+        use_cache: bool,
+    ) -> Result<(Place, usize)> {
+        let (_, places) = self.get_providers(use_cache)?.geocode(address)?;
+        let mut places = distinct_places(places, country);
+        let index = match pick {
+            _ if places.is_empty() => {
+                return Err(Error::LocationNotFound(format!(
+                    "None of the places matching '{}' is in {}",
+                    address,
+                    country.unwrap_or_default()
+                )))
+            }
+            Some(pick) => pick
+                .checked_sub(1)
+                .filter(|index| *index < places.len())
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "'--pick' should be from 1 to {}, since '{}' matches {} place(s)",
+                        places.len(),
+                        address,
+                        places.len()
+                    ))
+                })?,
+            None if places.len() == 1 => 0,
+            None if self.interactive => prompt_place(address, &places)?,
+            None => {
+                return Err(Error::InvalidInput(format!(
+                    "'{}' matches several places, pick one with '--pick N' or narrow them with '--country':\n{}",
+                    address,
+                    numbered_places(&places)
+                )))
+            }
+        };
+
+        let candidates = places.len();
+        Ok((places.swap_remove(index), candidates))
+    }
+
+    /// Requests weather for a single date or instant, or hour-by-hour weather of the date, and prints it.
+    /// Local date-times and times are interpreted in the '--tz' timezone, or in the location's one, which is
+    /// requested from the provider only if the resolved 'place' doesn't know it.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error in case of unparsable date, time or timezone, and backpropagates provider's errors.
+    fn get(
+        &self,
+        space_time_config: &SpaceTimeConfig,
+        place: Option<&Place>,
+        use_cache: bool,
+        format: OutputFormat,
+        units: Units,
+    ) -> Result<()> {
+        let address = space_time_config.address();
+        let location = describe_location(address, place);
+        let timezone = space_time_config
+            .tz
+            .as_deref()
+            .map(parse_timezone)
+            .transpose()?;
+        let known = known_timezone(space_time_config, place)?;
+        let location_timezone = || match known {
+            Some(timezone) => Ok(timezone),
+            None => self.get_providers(use_cache)?.get_timezone(address),
+        };
+
+        let mut moment: Option<Moment> = space_time_config
+            .date
+            .as_deref()
+            .map(str::parse)
+            .transpose()?;
+        if let Some(relative @ Moment::Relative(_)) = moment {
+            let today = Utc::now().with_timezone(&location_timezone()?).date_naive();
+            moment = Some(relative.resolve_date(today)?);
+        }
+        if let Some(ref time) = space_time_config.time {
+            match moment {
+                Some(Moment::Date(date)) => {
+                    moment = Some(Moment::LocalDateTime(date.and_time(parse_time(time)?)))
+                }
+                _ => {
+                    return Err(Error::InvalidInput(String::from(
+                        "'--time' requires a date(YYYY-MM-DD), not a date-time",
+                    )))
+                }
+            }
+        }
+
+        let (banner, provider_name, weathers) = match moment {
+            None => {
+                let (provider_name, weather) = self
+                    .get_providers(use_cache)?
+                    .get_current_weather(address)?;
+                let banner = format!(
+                    "-- Current weather for {} by {}: ",
+                    location,
+                    provider_name.get_pretty_name()
+                );
+                (banner, provider_name, vec![weather])
+            }
+            Some(Moment::Date(date)) if space_time_config.hourly => {
+                let (provider_name, hourly) = self
+                    .get_providers(use_cache)?
+                    .get_hourly_weather(address, date, known)?;
+                let banner = format!(
+                    "-- Hourly weather for {} on {} by {}: ",
+                    location,
+                    date,
+                    provider_name.get_pretty_name()
+                );
+                (banner, provider_name, hourly)
+            }
+            Some(Moment::Date(date)) => {
+                let providers = self.get_providers(use_cache)?;
+                let (provider_name, weather) =
+                    providers.get_timed_weather(address, &date.to_string(), known)?;
+                let banner = format!(
+                    "-- Weather for {} on {} by {}: ",
+                    location,
+                    date,
+                    provider_name.get_pretty_name()
+                );
+                (banner, provider_name, vec![weather])
+            }
+            Some(_) if space_time_config.hourly => {
+                return Err(Error::InvalidInput(String::from(
+                    "'--hourly' requires a date(YYYY-MM-DD), not a date-time",
+                )))
+            }
+            Some(moment) => {
+                let timezone = match timezone {
+                    Some(timezone) => timezone,
+                    None if moment.is_local() => location_timezone()?,
+                    None => Tz::UTC,
+                };
+                let instant = moment
+                    .resolve(timezone, Utc::now())?
+                    .expect("Only dates are resolved into the whole day");
+
+                let (provider_name, weather) = self
+                    .get_providers(use_cache)?
+                    .get_weather_at(address, instant, known)?;
+                let banner = format!(
+                    "-- Weather for {} at {} by {}: ",
+                    location,
+                    instant.with_timezone(&timezone).format("%Y-%m-%d %H:%M %Z"),
+                    provider_name.get_pretty_name()
+                );
+                (banner, provider_name, vec![weather])
+            }
+        };
+
+        let mut weathers = weathers;
+        let nearby = match place {
+            Some(_) => None,
+            None => self.nearby_place(address, &weathers, use_cache),
+        };
+        name_locations(&mut weathers, place.or(nearby.as_ref()));
+        let records: Vec<WeatherRecord> = weathers
+            .iter()
+            .map(|weather| WeatherRecord::new(provider_name, weather, units))
+            .collect();
+        print_weather(format, &banner, &records)
+    }
+
+    /// Returns the place nearby the 'address', if it's raw coordinates, which the provider left unnamed in the 'weathers'.
+    /// Failed lookup leaves them unnamed, since the weather is already there, e.g. in the ocean.
+    fn nearby_place(&self, address: &str, weathers: &[Weather], use_cache: bool) -> Option<Place> {
+        let coordinates = address.parse::<Coordinates>().ok()?;
+        if !weathers
+            .iter()
+            .any(|weather| weather.location.name.parse::<Coordinates>().is_ok())
+        {
+            return None;
+        }
+
+        let (_, places) = self
+            .get_providers(use_cache)
+            .ok()?
+            .reverse_geocode(coordinates)
+            .ok()?;
+        let nearby = places.into_iter().next()?;
+        Some(Place::named_after(coordinates, nearby))
+    }
+
+    /// Requests weather from every provider, which has api key, and prints comparison table.
+    /// Failed providers are reported and left out of the table.
+    ///
+    /// # Errors:
+    /// Returns 'Credentials' error, if none of the providers has api key, or the first provider's error, if all of them failed.
+    fn compare(
+        &self,
+        space_time_config: &SpaceTimeConfig,
+        place: Option<&Place>,
+        use_cache: bool,
+        format: OutputFormat,
+        units: Units,
+    ) -> Result<()> {
+        let secrets = SecretsConfig::load()?;
+        let cache = if use_cache {
+            Some(Cache::open(self.config.cache_ttl.clone())?)
+        } else {
+            None
+        };
+
+        let providers: Vec<(ProviderName, Box<dyn Provider>)> = ProviderName::iter()
+            .filter_map(|provider_name| {
+                let (api_key, _) = config::get_api_key(provider_name, &secrets).ok()?;
+                Some((
+                    provider_name,
+                    provider_name.get_provider_instance(
+                        api_key,
+                        cache.clone(),
+                        self.config.met_no_product,
+                    ),
+                ))
+            })
+            .collect();
+
+        self.compare_providers(space_time_config, place, providers, format, units)
+    }
+
+    fn compare_providers(
+        &self,
+        space_time_config: &SpaceTimeConfig,
+        place: Option<&Place>,
+        providers: Vec<(ProviderName, Box<dyn Provider>)>,
+        format: OutputFormat,
+        units: Units,
+    ) -> Result<()> {
+        if providers.is_empty() {
+            return Err(Error::Credentials(String::from(
+                "None of the providers has api key. Store them with 'keys set <provider> <api_key>'",
+            )));
+        }
+
+        let timezone = known_timezone(space_time_config, place)?;
+        let date = match space_time_config.date {
+            Some(ref date) => Some(resolve_compared_date(
+                date.parse()?,
+                space_time_config.address(),
+                timezone,
+                &providers,
+            )?),
+            None => None,
+        };
+
+        let mut reports = Vec::new();
+        let mut first_error = None;
+        for (provider_name, provider) in providers {
+            let weather = match date {
+                Some(date) => provider.get_timed_weather(
+                    space_time_config.address(),
+                    &date.to_string(),
+                    timezone,
+                ),
+                None => provider.get_current_weather(space_time_config.address()),
+            };
+
+            match weather {
+                Ok(weather) => reports.push((provider_name, weather)),
+                Err(err) => {
+                    eprintln!(
+                        "-- Provider {} failed: {}.",
+                        provider_name.get_pretty_name(),
+                        err
+                    );
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        if reports.is_empty() {
+            if let Some(err) = first_error {
+                return Err(err);
+            }
+        }
+
+        if format != OutputFormat::Table {
+            let records: Vec<WeatherRecord> = reports
+                .iter()
+                .map(|(provider_name, weather)| WeatherRecord::new(*provider_name, weather, units))
+                .collect();
+            println!("{}", format.render(&records)?);
+            return Ok(());
+        }
+
+        match date {
+            Some(date) => println!(
+                "-- Weather for {} on {} by {} providers: ",
+                space_time_config.address(),
+                date,
+                reports.len()
+            ),
+            None => println!(
+                "-- Current weather for {} by {} providers: ",
+                space_time_config.address(),
+                reports.len()
+            ),
+        }
+        // Providers might resolve the address into different places.
+        for (provider_name, weather) in &reports {
+            println!(
+                "-- {} resolved it to {}.",
+                provider_name.get_pretty_name(),
+                weather.location
+            );
+        }
+        println!("{}", Comparison::new(&reports, units));
+
+        Ok(())
+    }
+
+    fn process_keys_command(command: KeysSubcommand) -> Result<()> {
+        let mut secrets = SecretsConfig::load()?;
+
+        match command {
+            KeysSubcommand::Set {
+                provider_name,
+                api_key,
+            } => {
+                if !provider_name.requires_api_key() {
+                    println!(
+                        "-- {} requires no api key, so it wasn't stored.",
+                        provider_name.get_pretty_name()
+                    );
+                    return Ok(());
+                }
+                secrets.set(provider_name, api_key);
+                secrets.store()?;
                 println!(
-                    "-- Current provider: {}.",
-                    self.current_provider_name.get_pretty_name()
+                    "-- Api key for {} was successfully stored.",
+                    provider_name.get_pretty_name()
                 );
+                if std::env::var(provider_name.to_string()).is_ok() {
+                    println!(
+                        "-- Note: {} environment variable is set and overrides the stored key.",
+                        provider_name
+                    );
+                }
+            }
+            KeysSubcommand::List => {
+                for provider_name in ProviderName::iter() {
+                    match config::get_api_key(provider_name, &secrets) {
+                        Ok((_, ApiKeySource::NotRequired)) => {
+                            println!("-- {}: not required", provider_name.get_pretty_name())
+                        }
+                        Ok((api_key, source)) => println!(
+                            "-- {}: {} ({})",
+                            provider_name.get_pretty_name(),
+                            config::mask_api_key(&api_key),
+                            match source {
+                                ApiKeySource::Environment =>
+                                    format!("{} environment variable", provider_name),
+                                _ => String::from("stored"),
+                            }
+                        ),
+                        Err(_) => println!("-- {}: not set", provider_name.get_pretty_name()),
+                    }
+                }
+            }
+            KeysSubcommand::Test { provider_name } => {
+                let (api_key, _) = config::get_api_key(provider_name, &secrets)?;
+                provider_name
+                    .get_provider_instance(api_key, None, Product::default())
+                    .get_current_weather(TEST_ADDRESS)?;
+                if provider_name.requires_api_key() {
+                    println!(
+                        "-- Api key for {} is valid.",
+                        provider_name.get_pretty_name()
+                    );
+                } else {
+                    println!(
+                        "-- {} requires no api key and is reachable.",
+                        provider_name.get_pretty_name()
+                    );
+                }
+            }
+            KeysSubcommand::Remove { provider_name } => {
+                if secrets.remove(provider_name).is_some() {
+                    secrets.store()?;
+                    println!(
+                        "-- Api key for {} was successfully removed.",
+                        provider_name.get_pretty_name()
+                    );
+                } else {
+                    println!(
+                        "-- There is no stored api key for {}.",
+                        provider_name.get_pretty_name()
+                    );
+                }
+            }
+        }
 
-                Ok(())
+        Ok(())
+    }
+
+    fn process_places_command(&self, command: PlacesSubcommand, use_cache: bool) -> Result<()> {
+        let mut config = self.config.clone();
+
+        match command {
+            PlacesSubcommand::Add {
+                alias,
+                address,
+                pick,
+                country,
+            } => {
+                let providers = self.get_providers(use_cache)?;
+                let mut place = match address.parse::<Coordinates>() {
+                    Ok(coordinates) => providers
+                        .reverse_geocode(coordinates)
+                        .ok()
+                        .and_then(|(_, places)| places.into_iter().next())
+                        .map(|nearby| Place::named_after(coordinates, nearby))
+                        .unwrap_or_else(|| Place::at(coordinates)),
+                    Err(_) => {
+                        self.pick_place(&address, pick, country.as_deref(), use_cache)?
+                            .0
+                    }
+                };
+                if place.timezone.is_none() {
+                    let timezone = providers.get_timezone(&place.coordinates().to_string())?;
+                    place.timezone = Some(timezone.name().to_owned());
+                }
+
+                let description = describe_place(&place);
+                config.add_place(alias.clone(), place)?;
+                config.store()?;
+                println!("-- Place {} was saved as '{}'.", description, alias);
+            }
+            PlacesSubcommand::List => {
+                if config.places.is_empty() {
+                    println!("-- There are no saved places. Save one with 'places add <alias> <address>'.");
+                }
+                for (alias, place) in &config.places {
+                    println!("-- {}: {}", alias, describe_place(place));
+                }
+            }
+            PlacesSubcommand::Rename { alias, new_alias } => {
+                config.rename_place(&alias, new_alias.clone())?;
+                config.store()?;
+                println!("-- Place '{}' was renamed to '{}'.", alias, new_alias);
+            }
+            PlacesSubcommand::Remove { alias } => {
+                let place = config.remove_place(&alias)?;
+                config.store()?;
+                println!(
+                    "-- Place '{}' ({}) was successfully removed.",
+                    alias,
+                    describe_place(&place)
+                );
             }
         }
+
+        Ok(())
     }
 
-    fn get_available_providers() -> anyhow::Result<HashMap<ProviderName, String>> {
-        let mut available_providers = HashMap::<ProviderName, String>::new();
+    fn process_cache_command(&self, command: CacheSubcommand) -> Result<()> {
+        let cache = Cache::open(self.config.cache_ttl.clone())?;
 
-        for provider_name in ProviderName::iter() {
-            let api_key = std::env::var(provider_name.to_string()).with_context(|| {
-                format!(
-                    "Failed to get api key for {} provider. Check .env file in the current folder",
-                    provider_name
-                )
-            })?;
-            available_providers.insert(provider_name, api_key);
+        match command {
+            CacheSubcommand::Stats => {
+                let stats = cache.stats()?;
+                println!("-- Cache directory: {}", cache.directory().display());
+                println!(
+                    "-- Cached responses: {} ({} expired), {} bytes",
+                    stats.entries, stats.expired, stats.bytes
+                );
+                for (endpoint, count) in stats.entries_per_endpoint {
+                    let ttl = match self.config.cache_ttl.get(endpoint) {
+                        Some(ttl) => format!("expire in {} s", ttl),
+                        None => String::from("never expire"),
+                    };
+                    println!("--   {}: {} ({})", endpoint, count, ttl);
+                }
+            }
+            CacheSubcommand::Clear => {
+                let removed = cache.clear()?;
+                println!("-- Removed {} cached responses.", removed);
+            }
         }
 
-        Ok(available_providers)
+        Ok(())
+    }
+
+    /// Returns the '--provider' one, or the configured provider, followed by the fallback providers.
+    fn provider_chain(&self) -> Vec<ProviderName> {
+        let provider_name = self.provider_override.unwrap_or(self.config.provider_name);
+        self.config.provider_chain_from(provider_name)
     }
+
+    /// Lazily instantiates the current provider followed by the fallback providers.
+    /// Providers without api key are skipped.
+    ///
+    /// # Errors:
+    /// Returns 'Credentials' error of the current provider, if none of the providers has api key.
+    fn get_providers(&self, use_cache: bool) -> Result<&FallbackChain> {
+        if let Some(providers) = self.providers.get() {
+            return Ok(providers);
+        }
+
+        let secrets = SecretsConfig::load()?;
+        let cache = if use_cache {
+            Some(Cache::open(self.config.cache_ttl.clone())?)
+        } else {
+            None
+        };
+
+        let mut providers = Vec::new();
+        let mut first_error = None;
+        for provider_name in self.provider_chain() {
+            match config::get_api_key(provider_name, &secrets) {
+                Ok((api_key, _)) => providers.push((
+                    provider_name,
+                    provider_name.get_provider_instance(
+                        api_key,
+                        cache.clone(),
+                        self.config.met_no_product,
+                    ),
+                )),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        if providers.is_empty() {
+            if let Some(err) = first_error {
+                return Err(err);
+            }
+        }
+
+        Ok(self.providers.get_or_init(|| FallbackChain::new(providers)))
+    }
+}
+
+/// Resolves the date of 'compare', counting relative date from today in the known 'timezone',
+/// or in the location's one, reported by the first provider, which answers.
+///
+/// # Errors:
+/// Returns the last provider's error, if none of them resolved the timezone.
+fn resolve_compared_date(
+    moment: Moment,
+    address: &str,
+    timezone: Option<Tz>,
+    providers: &[(ProviderName, Box<dyn Provider>)],
+) -> Result<NaiveDate> {
+    let relative = match moment {
+        Moment::Date(date) => return Ok(date),
+        relative => relative,
+    };
+
+    let timezone = match timezone {
+        Some(timezone) => timezone,
+        None => {
+            let mut result = Err(Error::Config(String::from(
+                "There are no configured providers",
+            )));
+            for (_, provider) in providers {
+                result = provider.get_timezone(address);
+                if result.is_ok() {
+                    break;
+                }
+            }
+            result?
+        }
+    };
+
+    match relative.resolve_date(Utc::now().with_timezone(&timezone).date_naive())? {
+        Moment::Date(date) => Ok(date),
+        _ => Err(Error::InvalidInput(String::from(
+            "Date-times aren't supported by 'compare', use a date instead",
+        ))),
+    }
+}
+
+/// Returns places, which are in the 'country', if it's specified, leaving out places nearby the preceding ones,
+/// since weather is the same there.
+fn distinct_places(places: Vec<Place>, country: Option<&str>) -> Vec<Place> {
+    let mut distinct: Vec<Place> = Vec::new();
+    for place in places {
+        let in_country = match (country, &place.country) {
+            (Some(country), Some(place_country)) => place_country.eq_ignore_ascii_case(country),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let nearby = distinct.iter().any(|other| {
+            (other.latitude - place.latitude).abs() < SAME_PLACE_DEGREES
+                && (other.longitude - place.longitude).abs() < SAME_PLACE_DEGREES
+        });
+
+        if in_country && !nearby {
+            distinct.push(place);
+        }
+    }
+
+    distinct
+}
+
+/// Describes the 'place' along with its timezone.
+fn describe_place(place: &Place) -> String {
+    format!(
+        "{}, {}",
+        place,
+        place.timezone.as_deref().unwrap_or("unknown timezone")
+    )
+}
+
+/// Lists 'places' one per line, numbered from 1.
+fn numbered_places(places: &[Place]) -> String {
+    places
+        .iter()
+        .enumerate()
+        .map(|(index, place)| format!("{}. {}", index + 1, place))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Asks the user to pick one of the 'places' by its number, until a valid one is entered, and returns its index.
+/// The list and the question go to stderr, so they don't mix with the output.
+///
+/// # Errors:
+/// Returns 'InvalidInput' error, if the input ends before a place is picked.
+fn prompt_place(address: &str, places: &[Place]) -> Result<usize> {
+    eprintln!("-- '{}' matches several places:", address);
+    eprintln!("{}", numbered_places(places));
+
+    loop {
+        eprint!("-- Pick a place [1-{}]: ", places.len());
+        let mut input = String::new();
+        let read = std::io::stdin()
+            .read_line(&mut input)
+            .map_err(|error| Error::InvalidInput(format!("Unable to read the input: {}", error)))?;
+        if read == 0 {
+            return Err(Error::InvalidInput(format!(
+                "No place was picked for '{}'",
+                address
+            )));
+        }
+
+        match input.trim().parse::<usize>() {
+            Ok(pick) if (1..=places.len()).contains(&pick) => return Ok(pick - 1),
+            _ => eprintln!("-- Enter a number from 1 to {}.", places.len()),
+        }
+    }
+}
+
+/// Timezone of the location, which is known without provider's requests: the '--tz' one, or the one of the resolved 'place'.
+///
+/// # Errors:
+/// Returns 'InvalidInput' error for unknown '--tz' timezone.
+fn known_timezone(
+    space_time_config: &SpaceTimeConfig,
+    place: Option<&Place>,
+) -> Result<Option<Tz>> {
+    match space_time_config.tz {
+        Some(ref timezone) => parse_timezone(timezone).map(Some),
+        None => Ok(place
+            .and_then(|place| place.timezone.as_deref())
+            .and_then(|timezone| timezone.parse().ok())),
+    }
+}
+
+/// Describes the location for the banners: the resolved 'place', if there is one, or the 'address'.
+fn describe_location(address: &str, place: Option<&Place>) -> String {
+    place.map_or_else(|| address.to_owned(), Place::to_string)
+}
+
+/// Names locations of the 'weathers' after the resolved 'place', since the provider was requested with its
+/// coordinates. Coordinates and timezone, which the provider reports, are kept.
+fn name_locations(weathers: &mut [Weather], place: Option<&Place>) {
+    let Some(place) = place else {
+        return;
+    };
+
+    for weather in weathers {
+        let timezone = weather
+            .location
+            .timezone
+            .take()
+            .or_else(|| place.timezone.clone());
+        weather.location = Place {
+            timezone,
+            ..Place::named_after(weather.location.coordinates(), place.clone())
+        };
+    }
+}
+
+/// Parses and validates date range, which shouldn't exceed 'MAX_SERIES_DAYS'.
+fn parse_date_range(from: &str, to: &str) -> Result<(NaiveDate, NaiveDate)> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+            Error::InvalidInput(format!(
+                "Entered date '{}' should be in the YYYY-MM-DD format",
+                date
+            ))
+        })
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+
+    if from > to {
+        return Err(Error::InvalidInput(format!(
+            "Range start {} is after its end {}",
+            from, to
+        )));
+    }
+    if (to - from).num_days() >= MAX_SERIES_DAYS {
+        return Err(Error::InvalidInput(format!(
+            "Range shouldn't be longer than {} days",
+            MAX_SERIES_DAYS
+        )));
+    }
+
+    Ok((from, to))
+}
+
+/// Prints weather records in the format. Banner precedes only the human readable table.
+fn print_weather(format: OutputFormat, banner: &str, records: &[WeatherRecord]) -> Result<()> {
+    let output = format.render(records)?;
+    if format == OutputFormat::Table {
+        println!("{}", banner);
+    }
+    println!("{}", output);
+
+    Ok(())
+}
+
+/// Joins pretty names of the providers, e.g. 'open-weather-map, weather-api'.
+fn pretty_names(provider_names: &[ProviderName]) -> String {
+    provider_names
+        .iter()
+        .map(ProviderName::get_pretty_name)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[cfg(test)]
@@ -185,14 +1360,766 @@ mod test {
     use dotenvy::dotenv;
 
     use super::*;
+    use crate::mock_server::{fixture, MockServer};
+    use crate::provider::open_weather_map::OpenWeatherMap;
+    use crate::provider::weather_api::WeatherApi;
+
+    fn mocked_agent(server: &MockServer) -> PromptAgent {
+        server
+            .route("/current.json", 200, &fixture("weather_api/current.json"))
+            .route("/history.json", 200, &fixture("weather_api/history.json"))
+            .route(
+                "/search.json",
+                200,
+                &fixture("weather_api/search_single.json"),
+            );
+        let provider = WeatherApi::with_base_url(String::from("test_key"), server.url());
+        PromptAgent::with_provider(ProviderName::WeatherApi, Box::new(provider))
+    }
 
     #[test]
-    fn test_get_available_providers() {
-        dotenv().ok();
-        let available_providers = PromptAgent::get_available_providers().unwrap();
-        for provider in ProviderName::iter() {
-            assert!(available_providers.contains_key(&provider));
+    fn test_process_command_get_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: None,
+                from: None,
+                to: None,
+                time: None,
+                hourly: false,
+                tz: None,
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: Some(String::from("2023-04-06")),
+                from: None,
+                to: None,
+                time: None,
+                hourly: false,
+                tz: None,
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+        // Each address is geocoded once, then requested by its coordinates.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[0].starts_with("/search.json"));
+        assert!(!requests[1].starts_with("/search.json"));
+        assert!(requests[2].starts_with("/search.json"));
+        assert!(!requests[3].starts_with("/search.json"));
+    }
+
+    #[test]
+    fn test_process_command_get_range_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: None,
+                from: Some(String::from("2023-04-04")),
+                to: Some(String::from("2023-04-06")),
+                time: None,
+                hourly: false,
+                tz: None,
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn test_application_command() {
+        use clap::CommandFactory;
+
+        Application::command().debug_assert();
+
+        let application = Application::try_parse_from([
+            "elastio_task",
+            "get",
+            "Lviv",
+            "--from",
+            "2023-04-01",
+            "--to",
+            "2023-04-07",
+        ])
+        .unwrap();
+        assert!(matches!(
+            application.command,
+            InputSubcommand::Get(SpaceTimeConfig { from: Some(_), .. })
+        ));
+
+        let result = Application::try_parse_from([
+            "elastio_task",
+            "get",
+            "Lviv",
+            "2023-04-01",
+            "--from",
+            "2023-04-01",
+        ]);
+        assert!(result.is_err());
+
+        let application = Application::try_parse_from([
+            "elastio_task",
+            "get",
+            "Lviv",
+            "--provider",
+            "weather-api",
+        ])
+        .unwrap();
+        assert_eq!(application.options.provider, Some(ProviderName::WeatherApi));
+    }
+
+    #[test]
+    fn test_provider_override() {
+        let provider = WeatherApi::new(String::from("test_key"));
+        let mut agent = PromptAgent::with_provider(ProviderName::WeatherApi, Box::new(provider));
+        agent.config.fallback = vec![ProviderName::WeatherApi];
+        agent.provider_override = Some(ProviderName::OpenWeatherMap);
+
+        // Fallback providers are still tried, while the configured one is kept stored.
+        assert_eq!(
+            agent.provider_chain(),
+            vec![ProviderName::OpenWeatherMap, ProviderName::WeatherApi]
+        );
+        assert_eq!(agent.config.provider_name, ProviderName::WeatherApi);
+    }
+
+    #[test]
+    fn test_process_command_get_hourly_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+
+        for (time, hourly) in [(None, true), (Some(String::from("15:00")), false)] {
+            let result = agent.process_command(Application {
+                command: InputSubcommand::Get(SpaceTimeConfig {
+                    address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                    date: Some(String::from("2023-04-06")),
+                    from: None,
+                    to: None,
+                    time,
+                    hourly,
+                    tz: None,
+                    lat: None,
+                    lon: None,
+                    pick: None,
+                    country: None,
+                }),
+                options: GlobalOptions::default(),
+            });
+            assert!(result.is_ok());
+        }
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: Some(String::from("2023-04-06")),
+                from: None,
+                to: None,
+                time: Some(String::from("3 PM")),
+                hourly: false,
+                tz: None,
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_process_command_get_date_time_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+        let get = |date: &str, tz: Option<&str>| {
+            agent.process_command(Application {
+                command: InputSubcommand::Get(SpaceTimeConfig {
+                    address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                    date: Some(date.to_owned()),
+                    from: None,
+                    to: None,
+                    time: None,
+                    hourly: false,
+                    tz: tz.map(str::to_owned),
+                    lat: None,
+                    lon: None,
+                    pick: None,
+                    country: None,
+                }),
+                options: GlobalOptions::default(),
+            })
+        };
+
+        // Local date-time is interpreted in the location's timezone, which is resolved from the current weather.
+        assert!(get("2023-04-06T15:00", None).is_ok());
+        assert!(server.requests()[1].starts_with("/current.json"));
+        assert!(server.requests().last().unwrap().contains("dt=2023-04-06"));
+
+        assert!(get("2023-04-06 15:00+03:00", None).is_ok());
+        assert!(get("2023-04-06T15:00", Some("America/New_York")).is_ok());
+        assert!(server.requests().last().unwrap().contains("dt=2023-04-06"));
+
+        assert!(matches!(
+            get("2023-04-06T15:00", Some("Europe/Atlantis")),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            get("06.04.2023", None),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_command_get_relative_date_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+        let two_days_ago = || {
+            (Utc::now().with_timezone(&chrono_tz::Pacific::Kiritimati) - Duration::days(2))
+                .date_naive()
+        };
+
+        let before = two_days_ago();
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: Some(String::from("2 days ago")),
+                from: None,
+                to: None,
+                time: None,
+                hourly: false,
+                tz: Some(String::from("Pacific/Kiritimati")),
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+
+        let after = two_days_ago();
+
+        // The date is counted from today in the '--tz' timezone, which might differ from the machine's one.
+        // The timezone is known, so it isn't requested from the provider.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("/history.json"));
+        assert!([before, after]
+            .iter()
+            .any(|date| requests[1].contains(&format!("dt={}", date))));
+    }
+
+    /// Requests weather for the relative 'date', counted in the timezone of the fixtures' location.
+    fn get_relative_date_mocked(server: &MockServer, date: &str) {
+        let agent = mocked_agent(server);
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: Some(date.to_owned()),
+                from: None,
+                to: None,
+                time: None,
+                hourly: false,
+                tz: Some(String::from("Europe/Kiev")),
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_process_command_get_yesterday_mocked() {
+        let server = MockServer::start();
+        get_relative_date_mocked(&server, "yesterday");
+
+        let yesterday =
+            (Utc::now().with_timezone(&chrono_tz::Europe::Kiev) - Duration::days(1)).date_naive();
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.starts_with("/history.json")
+                && request.contains(&format!("dt={}", yesterday))));
+    }
+
+    #[test]
+    fn test_process_command_get_tomorrow_mocked() {
+        let today = Utc::now()
+            .with_timezone(&chrono_tz::Europe::Kiev)
+            .date_naive();
+        // The last day of the forecast is the requested one.
+        let forecast = fixture("weather_api/forecast.json")
+            .replace("2023-04-07", &(today - Duration::days(1)).to_string())
+            .replace("2023-04-08", &today.to_string())
+            .replace("2023-04-09", &(today + Duration::days(1)).to_string());
+        let server = MockServer::start();
+        server.route("/forecast.json", 200, &forecast);
+        get_relative_date_mocked(&server, "tomorrow");
+
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.starts_with("/forecast.json")));
+    }
+
+    #[test]
+    fn test_process_command_get_coordinates_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+
+        let application = Application::try_parse_from([
+            "elastio_task",
+            "get",
+            "--lat",
+            "49.84",
+            "--lon",
+            "24.03",
+            "2023-04-06",
+        ])
+        .unwrap();
+        assert!(agent.process_command(application).is_ok());
+        let requests = server.requests();
+        assert!(requests[0].contains("q=49.84%2C24.03"));
+        assert!(requests[0].contains("dt=2023-04-06"));
+
+        let application = Application::try_parse_from([
+            "elastio_task",
+            "get",
+            "Lviv",
+            "2023-04-06",
+            "--lat",
+            "49.84",
+            "--lon",
+            "24.03",
+        ])
+        .unwrap();
+        assert!(matches!(
+            agent.process_command(application),
+            Err(Error::InvalidInput(_))
+        ));
+
+        let application =
+            Application::try_parse_from(["elastio_task", "get", "--lat", "91", "--lon", "24.03"])
+                .unwrap();
+        assert!(matches!(
+            agent.process_command(application),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_command_locate_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+        server.route("/search.json", 200, &fixture("weather_api/search.json"));
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Locate {
+                query: Some(String::from("Mykolaiv")),
+                reverse: None,
+            },
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+
+        // Candidates are listed as geocoded, without a weather request per candidate.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("/search.json"));
+
+        let application =
+            Application::try_parse_from(["elastio_task", "locate", "--reverse", "-33.87,151.21"])
+                .unwrap();
+        assert!(agent.process_command(application).is_ok());
+        assert!(server.requests()[1].contains("q=-33.87%2C151.21"));
+    }
+
+    #[test]
+    fn test_process_command_get_nearby_place_mocked() {
+        let server = MockServer::start();
+        server
+            .route(
+                "/geo/1.0/direct",
+                200,
+                &fixture("open_weather_map/geocoding.json"),
+            )
+            .route(
+                "/geo/1.0/reverse",
+                200,
+                &fixture("open_weather_map/reverse_geocoding.json"),
+            )
+            .route(
+                "/data/3.0/onecall",
+                200,
+                &fixture("open_weather_map/onecall.json"),
+            );
+        let provider = OpenWeatherMap::with_base_url(String::from("test_key"), server.url());
+        let agent = PromptAgent::with_provider(ProviderName::OpenWeatherMap, Box::new(provider));
+        let get = |address: &str| {
+            let application =
+                Application::try_parse_from(["elastio_task", "get", address]).unwrap();
+            assert!(agent.process_command(application).is_ok());
+        };
+
+        // Raw coordinates, which open-weather-map can't name, are reverse geocoded once.
+        get("49.84,24.03");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("/data/3.0/onecall"));
+        assert!(requests[1].starts_with("/geo/1.0/reverse"));
+
+        // Geocoded address is already named.
+        get("Mykolaiv, Lviv oblast, Ukraine");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[2].starts_with("/geo/1.0/direct"));
+        assert!(requests[3].starts_with("/data/3.0/onecall"));
+
+        // Coordinates, which weather-api names itself, aren't reverse geocoded.
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+        let application =
+            Application::try_parse_from(["elastio_task", "get", "49.52,23.98"]).unwrap();
+        assert!(agent.process_command(application).is_ok());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("/current.json"));
+    }
+
+    #[test]
+    fn test_process_command_get_ambiguous_mocked() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+        server.route("/search.json", 200, &fixture("weather_api/search.json"));
+        let get = |pick: Option<usize>, country: Option<&str>| {
+            agent.process_command(Application {
+                command: InputSubcommand::Get(SpaceTimeConfig {
+                    address: Some(String::from("Mykolaiv")),
+                    date: Some(String::from("2023-04-06")),
+                    from: None,
+                    to: None,
+                    time: None,
+                    hourly: false,
+                    tz: None,
+                    lat: None,
+                    lon: None,
+                    pick,
+                    country: country.map(str::to_owned),
+                }),
+                options: GlobalOptions::default(),
+            })
+        };
+
+        // The user can't be asked in tests, so the candidates are listed in the error.
+        match get(None, None) {
+            Err(Error::InvalidInput(message)) => {
+                assert!(message.contains("1. Mykolaiv, L'vivs'ka Oblast', Ukraine (49.52, 23.98)"));
+                assert!(message.contains("2. Mykolayiv"));
+            }
+            result => panic!("Unexpected result: {:?}", result),
         }
+
+        assert!(get(Some(2), None).is_ok());
+        assert!(server.requests().last().unwrap().contains("q=46.97%2C32"));
+        assert!(matches!(get(Some(3), None), Err(Error::InvalidInput(_))));
+
+        // Both places are in Ukraine, so the country doesn't narrow them.
+        assert!(matches!(
+            get(None, Some("ukraine")),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            get(None, Some("Poland")),
+            Err(Error::LocationNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_command_get_saved_place_mocked() {
+        let server = MockServer::start();
+        let mut agent = mocked_agent(&server);
+        agent.config.places.insert(
+            String::from("office"),
+            Place {
+                timezone: Some(String::from("Europe/Kyiv")),
+                ..Place::at(Coordinates::new(49.84, 24.03).unwrap())
+            },
+        );
+
+        let application =
+            Application::try_parse_from(["elastio_task", "get", "office", "2023-04-06"]).unwrap();
+        assert!(agent.process_command(application).is_ok());
+
+        // The alias isn't geocoded, but resolved into the saved coordinates.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(
+            requests[0].starts_with("/history.json") && requests[0].contains("q=49.84%2C24.03")
+        );
+    }
+
+    #[test]
+    fn test_resolve_default_location() {
+        let parse = |args: &[&str]| {
+            let application =
+                Application::try_parse_from([&["elastio_task", "get"], args].concat()).unwrap();
+            match application.command {
+                InputSubcommand::Get(space_time_config) => space_time_config,
+                _ => unreachable!(),
+            }
+        };
+
+        let config = parse(&[]).resolve_location(Some("office")).unwrap();
+        assert_eq!(config.address(), "office");
+        assert_eq!(config.date, None);
+
+        // The only positional argument is the date, if it's a date.
+        let config = parse(&["next friday"])
+            .resolve_location(Some("office"))
+            .unwrap();
+        assert_eq!(config.address(), "office");
+        assert_eq!(config.date.as_deref(), Some("next friday"));
+
+        let config = parse(&["Lviv"]).resolve_location(Some("office")).unwrap();
+        assert_eq!(config.address(), "Lviv");
+        assert_eq!(config.date, None);
+
+        let config = parse(&["Lviv", "tomorrow"])
+            .resolve_location(Some("office"))
+            .unwrap();
+        assert_eq!(config.address(), "Lviv");
+
+        let config = parse(&["2023-04-06", "--hourly"])
+            .resolve_location(Some("office"))
+            .unwrap();
+        assert_eq!(config.date.as_deref(), Some("2023-04-06"));
+        assert!(matches!(
+            parse(&["Lviv", "--time", "15:00"]).resolve_location(Some("office")),
+            Err(Error::InvalidInput(_))
+        ));
+
+        assert!(matches!(
+            parse(&["tomorrow"]).resolve_location(None),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            parse(&["2023-04-01", "--from", "2023-04-01", "--to", "2023-04-02"])
+                .resolve_location(Some("office")),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_distinct_places() {
+        let place = |name: &str, country: &str, latitude: f64| Place {
+            name: name.to_owned(),
+            region: None,
+            country: Some(country.to_owned()),
+            latitude,
+            longitude: 24.0,
+            timezone: None,
+        };
+        let places = vec![
+            place("Lviv", "UA", 49.84),
+            place("Lviv city", "UA", 49.83),
+            place("Lwów", "PL", 50.5),
+        ];
+
+        assert_eq!(distinct_places(places.clone(), None).len(), 2);
+        assert_eq!(distinct_places(places.clone(), Some("pl"))[0].name, "Lwów");
+        assert!(distinct_places(places, Some("US")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        assert!(parse_date_range("2023-04-01", "2023-04-01").is_ok());
+        assert!(matches!(
+            parse_date_range("2023-04-07", "2023-04-01"),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            parse_date_range("2023-01-01", "2023-04-01"),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            parse_date_range("2023-04-01", "04/07/2023"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_command_get_mocked_invalid_address() {
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
+        server.route(
+            "/current.json",
+            400,
+            &fixture("weather_api/error_1006.json"),
+        );
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("SO INVALID ADDRESS")),
+                date: None,
+                from: None,
+                to: None,
+                time: None,
+                hourly: false,
+                tz: None,
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(matches!(result, Err(Error::LocationNotFound(_))));
+    }
+
+    #[test]
+    fn test_process_command_get_mocked_fallback() {
+        let failing_server = MockServer::start();
+        failing_server.route(
+            "/history.json",
+            400,
+            &fixture("weather_api/error_2007.json"),
+        );
+        failing_server.route(
+            "/search.json",
+            200,
+            &fixture("weather_api/search_single.json"),
+        );
+        let server = MockServer::start();
+        let agent = PromptAgent::with_providers(vec![
+            (
+                ProviderName::WeatherApi,
+                Box::new(WeatherApi::with_base_url(
+                    String::from("test_key"),
+                    failing_server.url(),
+                )),
+            ),
+            (
+                ProviderName::WeatherApi,
+                Box::new(WeatherApi::with_base_url(
+                    String::from("test_key"),
+                    server.url(),
+                )),
+            ),
+        ]);
+        server.route("/history.json", 200, &fixture("weather_api/history.json"));
+
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Get(SpaceTimeConfig {
+                address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+                date: Some(String::from("2023-04-06")),
+                from: None,
+                to: None,
+                time: None,
+                hourly: false,
+                tz: None,
+                lat: None,
+                lon: None,
+                pick: None,
+                country: None,
+            }),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+        assert_eq!(failing_server.requests().len(), 2);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_compare_providers_mocked() {
+        let server = MockServer::start();
+        server.route("/current.json", 200, &fixture("weather_api/current.json"));
+        let failing_server = MockServer::start();
+        failing_server.route(
+            "/current.json",
+            400,
+            &fixture("weather_api/error_2007.json"),
+        );
+        let agent = mocked_agent(&server);
+
+        let space_time_config = SpaceTimeConfig {
+            address: Some(String::from("Mykolaiv, Lviv oblast, Ukraine")),
+            date: None,
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
+        };
+        let result = agent.compare_providers(
+            &space_time_config,
+            None,
+            vec![
+                (
+                    ProviderName::WeatherApi,
+                    Box::new(WeatherApi::with_base_url(
+                        String::from("test_key"),
+                        server.url(),
+                    )),
+                ),
+                (
+                    ProviderName::WeatherApi,
+                    Box::new(WeatherApi::with_base_url(
+                        String::from("test_key"),
+                        failing_server.url(),
+                    )),
+                ),
+            ],
+            OutputFormat::Table,
+            Units::Imperial,
+        );
+        assert!(result.is_ok());
+
+        let result = agent.compare_providers(
+            &space_time_config,
+            None,
+            vec![(
+                ProviderName::WeatherApi,
+                Box::new(WeatherApi::with_base_url(
+                    String::from("test_key"),
+                    failing_server.url(),
+                )),
+            )],
+            OutputFormat::Json,
+            Units::Metric,
+        );
+        assert!(matches!(result, Err(Error::QuotaExceeded(_))));
     }
 
     #[test]
@@ -201,12 +2128,22 @@ mod test {
         dotenv().ok();
         let agent = PromptAgent::new().unwrap();
         let space_time_config = SpaceTimeConfig {
-            address: String::from("L'aquila, Italy"),
+            address: Some(String::from("L'aquila, Italy")),
             date: None,
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
         };
 
         let result = agent.process_command(Application {
             command: InputSubcommand::Get(space_time_config),
+            options: GlobalOptions::default(),
         });
         assert!(result.is_ok());
     }
@@ -222,11 +2159,21 @@ mod test {
         let formatted_tomorrow = tomorrow.format("%Y-%m-%d");
 
         let space_time_config = SpaceTimeConfig {
-            address: String::from("Palermo, Italy"),
+            address: Some(String::from("Palermo, Italy")),
             date: Some(formatted_tomorrow.to_string()),
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
         };
         let result = agent.process_command(Application {
             command: InputSubcommand::Get(space_time_config),
+            options: GlobalOptions::default(),
         });
         assert!(result.is_ok());
     }
@@ -242,11 +2189,21 @@ mod test {
         let formatted_yesterday = yesterday.format("%Y-%m-%d");
 
         let space_time_config = SpaceTimeConfig {
-            address: String::from("Palermo, Italy"),
+            address: Some(String::from("Palermo, Italy")),
             date: Some(formatted_yesterday.to_string()),
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
         };
         let result = agent.process_command(Application {
             command: InputSubcommand::Get(space_time_config),
+            options: GlobalOptions::default(),
         });
         assert!(result.is_ok());
     }
@@ -258,12 +2215,22 @@ mod test {
         let agent = PromptAgent::new().unwrap();
 
         let space_time_config = SpaceTimeConfig {
-            address: String::from("SO INVALID ADDRESS"),
+            address: Some(String::from("SO INVALID ADDRESS")),
             date: None,
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
         };
 
         let result = agent.process_command(Application {
             command: InputSubcommand::Get(space_time_config),
+            options: GlobalOptions::default(),
         });
 
         assert!(result.is_err());
@@ -276,12 +2243,22 @@ mod test {
         let agent = PromptAgent::new().unwrap();
 
         let space_time_config = SpaceTimeConfig {
-            address: String::from("São Paulo"),
+            address: Some(String::from("São Paulo")),
             date: Some(String::from("1800-12-12")),
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
         };
 
         let result = agent.process_command(Application {
             command: InputSubcommand::Get(space_time_config),
+            options: GlobalOptions::default(),
         });
 
         assert!(result.is_err());
@@ -291,15 +2268,37 @@ mod test {
     fn test_process_command_configure() {
         dotenv().ok();
         let agent = PromptAgent::new().unwrap();
-        let current_provider = agent.current_provider_name.clone();
+        let current_provider = agent.config.provider_name;
 
         let result = agent.process_command(Application {
             command: InputSubcommand::Configure(ProviderName::OpenWeatherMap),
+            options: GlobalOptions::default(),
         });
         assert!(result.is_ok());
 
         let result = agent.process_command(Application {
             command: InputSubcommand::Configure(current_provider),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_process_command_cache_stats() {
+        let agent = PromptAgent::new().unwrap();
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Cache(CacheSubcommand::Stats),
+            options: GlobalOptions::default(),
+        });
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_process_command_keys_list() {
+        let agent = PromptAgent::new().unwrap();
+        let result = agent.process_command(Application {
+            command: InputSubcommand::Keys(KeysSubcommand::List),
+            options: GlobalOptions::default(),
         });
         assert!(result.is_ok());
     }
@@ -310,24 +2309,37 @@ mod test {
         let agent = PromptAgent::new().unwrap();
         let result = agent.process_command(Application {
             command: InputSubcommand::CurrentProvider,
+            options: GlobalOptions::default(),
         });
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_process_command_with_invalid_date_format() {
-        dotenv().ok();
-        let agent = PromptAgent::new().unwrap();
+        let server = MockServer::start();
+        let agent = mocked_agent(&server);
 
         let space_time_config = SpaceTimeConfig {
-            address: String::from("São Paulo"),
+            address: Some(String::from("São Paulo")),
             date: Some(String::from("2000-12-32")),
+            from: None,
+            to: None,
+            time: None,
+            hourly: false,
+            tz: None,
+            lat: None,
+            lon: None,
+            pick: None,
+            country: None,
         };
 
         let result = agent.process_command(Application {
             command: InputSubcommand::Get(space_time_config),
+            options: GlobalOptions::default(),
         });
 
-        assert!(result.is_err());
+        // Invalid date is rejected before the address is geocoded.
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert!(server.requests().is_empty());
     }
 }
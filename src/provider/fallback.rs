@@ -0,0 +1,295 @@
+//! Ordered chain of providers, which falls back to the next provider, when the previous one fails.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use super::{Coordinates, Place, Provider, ProviderName, Weather};
+use crate::error::{Error, Result};
+
+/// Ordered list of providers. Requests are performed by the first provider, and passed to the next one
+/// only if the previous provider failed with a retryable error or doesn't support the requested date.
+pub struct FallbackChain {
+    providers: Vec<(ProviderName, Box<dyn Provider>)>,
+}
+
+impl FallbackChain {
+    /// Creates chain from the providers in the order they should be tried.
+    pub fn new(providers: Vec<(ProviderName, Box<dyn Provider>)>) -> FallbackChain {
+        FallbackChain { providers }
+    }
+
+    /// Retrieves current weather at the 'address' along with the name of the provider, which answered.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn get_current_weather(&self, address: &str) -> Result<(ProviderName, Weather)> {
+        self.request(|provider| provider.get_current_weather(address))
+    }
+
+    /// Retrieves weather at the 'address' on the 'date' along with the name of the provider, which answered.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn get_timed_weather(
+        &self,
+        address: &str,
+        date: &str,
+        timezone: Option<Tz>,
+    ) -> Result<(ProviderName, Weather)> {
+        self.request(|provider| provider.get_timed_weather(address, date, timezone))
+    }
+
+    /// Retrieves hour-by-hour weather at the 'address' on the 'date' along with the name of the provider, which answered.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn get_hourly_weather(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<(ProviderName, Vec<Weather>)> {
+        self.request(|provider| provider.get_hourly_weather(address, date, timezone))
+    }
+
+    /// Retrieves weather at the 'address' for the hour of the 'instant' along with the name of the provider, which answered.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn get_weather_at(
+        &self,
+        address: &str,
+        instant: DateTime<Utc>,
+        timezone: Option<Tz>,
+    ) -> Result<(ProviderName, Weather)> {
+        self.request(|provider| provider.get_weather_at(address, instant, timezone))
+    }
+
+    /// Returns places, which match the 'query', along with the name of the provider, which answered.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn geocode(&self, query: &str) -> Result<(ProviderName, Vec<Place>)> {
+        self.request(|provider| provider.geocode(query))
+    }
+
+    /// Returns places nearby the 'coordinates', along with the name of the provider, which answered.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn reverse_geocode(&self, coordinates: Coordinates) -> Result<(ProviderName, Vec<Place>)> {
+        self.request(|provider| provider.reverse_geocode(coordinates))
+    }
+
+    /// Resolves timezone of the 'address' with the first provider, which answers.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn get_timezone(&self, address: &str) -> Result<Tz> {
+        self.request(|provider| provider.get_timezone(address))
+            .map(|(_, timezone)| timezone)
+    }
+
+    /// Retrieves weather at the 'address' for every day from 'from' to 'to', along with the name of the provider, which answered.
+    /// The whole series is provided by a single provider.
+    ///
+    /// # Errors:
+    /// Returns the first non-retryable error, or the error of the last provider, if all of them failed.
+    pub fn get_weather_series(
+        &self,
+        address: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<(ProviderName, Vec<Weather>)> {
+        self.request(|provider| provider.get_weather_series(address, from, to, timezone))
+    }
+
+    fn request<T>(
+        &self,
+        request: impl Fn(&dyn Provider) -> Result<T>,
+    ) -> Result<(ProviderName, T)> {
+        let mut last_error = None;
+
+        for (index, (provider_name, provider)) in self.providers.iter().enumerate() {
+            match request(provider.as_ref()) {
+                Ok(value) => return Ok((*provider_name, value)),
+                Err(err) if err.is_retryable() => {
+                    if let Some((next_provider_name, _)) = self.providers.get(index + 1) {
+                        eprintln!(
+                            "-- Provider {} failed: {}. Falling back to {}.",
+                            provider_name.get_pretty_name(),
+                            err,
+                            next_provider_name.get_pretty_name()
+                        );
+                    }
+                    last_error = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| Error::Config(String::from("There are no configured providers"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::provider::{Condition, ConditionKind, Geocoder, Wind};
+
+    /// Provider, which either fails with the specified error, or answers with a fixed report.
+    struct StubProvider {
+        error: Option<fn(String) -> Error>,
+        calls: Rc<Cell<usize>>,
+    }
+
+    impl Geocoder for StubProvider {
+        fn geocode(&self, query: &str) -> Result<Vec<Place>> {
+            Ok(vec![self.get_current_weather(query)?.location])
+        }
+
+        fn reverse_geocode(&self, coordinates: Coordinates) -> Result<Vec<Place>> {
+            self.geocode(&coordinates.to_string())
+        }
+    }
+
+    impl Provider for StubProvider {
+        fn get_current_weather(&self, address: &str) -> Result<Weather> {
+            self.calls.set(self.calls.get() + 1);
+            match self.error {
+                Some(error) => Err(error(String::from("stub"))),
+                None => Ok(Weather {
+                    location: Place {
+                        name: address.to_owned(),
+                        region: None,
+                        country: None,
+                        latitude: 0.0,
+                        longitude: 0.0,
+                        timezone: None,
+                    },
+                    observed_at: chrono::Utc::now(),
+                    temperature: 20.0,
+                    feels_like: None,
+                    humidity: None,
+                    pressure: None,
+                    precipitation: None,
+                    visibility: None,
+                    wind: Wind {
+                        speed: None,
+                        direction: None,
+                    },
+                    condition: Condition {
+                        kind: ConditionKind::Clear,
+                        description: String::from("Clear"),
+                    },
+                }),
+            }
+        }
+
+        fn get_timed_weather(
+            &self,
+            address: &str,
+            _date: &str,
+            _timezone: Option<Tz>,
+        ) -> Result<Weather> {
+            self.get_current_weather(address)
+        }
+
+        fn get_hourly_weather(
+            &self,
+            address: &str,
+            _date: NaiveDate,
+            _timezone: Option<Tz>,
+        ) -> Result<Vec<Weather>> {
+            Ok(vec![self.get_current_weather(address)?])
+        }
+
+        fn get_weather_at(
+            &self,
+            address: &str,
+            _instant: DateTime<Utc>,
+            _timezone: Option<Tz>,
+        ) -> Result<Weather> {
+            self.get_current_weather(address)
+        }
+
+        fn get_timezone(&self, _address: &str) -> Result<Tz> {
+            Ok(Tz::UTC)
+        }
+    }
+
+    fn stub(error: Option<fn(String) -> Error>) -> (Box<dyn Provider>, Rc<Cell<usize>>) {
+        let calls = Rc::new(Cell::new(0));
+        let provider = StubProvider {
+            error,
+            calls: Rc::clone(&calls),
+        };
+        (Box::new(provider), calls)
+    }
+
+    #[test]
+    fn test_fallback_on_retryable_errors() {
+        let (network, _) = stub(Some(Error::Network));
+        let (date, _) = stub(Some(Error::DateOutOfRange));
+        let (answering, answering_calls) = stub(None);
+        let chain = FallbackChain::new(vec![
+            (ProviderName::OpenWeatherMap, network),
+            (ProviderName::WeatherApi, date),
+            (ProviderName::WeatherApi, answering),
+        ]);
+
+        let (provider_name, weather) = chain.get_timed_weather("Lviv", "2023-04-06", None).unwrap();
+        assert_eq!(provider_name, ProviderName::WeatherApi);
+        assert_eq!(weather.location.name, "Lviv");
+        assert_eq!(answering_calls.get(), 1);
+    }
+
+    #[test]
+    fn test_fallback_on_unsupported_reverse_geocoding() {
+        let (unsupported, _) = stub(Some(Error::Unsupported));
+        let (answering, answering_calls) = stub(None);
+        let chain = FallbackChain::new(vec![
+            (ProviderName::OpenMeteo, unsupported),
+            (ProviderName::OpenWeatherMap, answering),
+        ]);
+
+        let (provider_name, places) = chain
+            .reverse_geocode("49.84,24.03".parse().unwrap())
+            .unwrap();
+        assert_eq!(provider_name, ProviderName::OpenWeatherMap);
+        assert!(!places.is_empty());
+        assert_eq!(answering_calls.get(), 1);
+    }
+
+    #[test]
+    fn test_no_fallback_on_non_retryable_error() {
+        let (not_found, _) = stub(Some(Error::LocationNotFound));
+        let (answering, answering_calls) = stub(None);
+        let chain = FallbackChain::new(vec![
+            (ProviderName::OpenWeatherMap, not_found),
+            (ProviderName::WeatherApi, answering),
+        ]);
+
+        let result = chain.get_current_weather("SO INVALID ADDRESS");
+        assert!(matches!(result, Err(Error::LocationNotFound(_))));
+        assert_eq!(answering_calls.get(), 0);
+    }
+
+    #[test]
+    fn test_all_providers_failed() {
+        let (network, _) = stub(Some(Error::Network));
+        let (quota, _) = stub(Some(Error::QuotaExceeded));
+        let chain = FallbackChain::new(vec![
+            (ProviderName::OpenWeatherMap, network),
+            (ProviderName::WeatherApi, quota),
+        ]);
+
+        let result = chain.get_current_weather("Lviv");
+        assert!(matches!(result, Err(Error::QuotaExceeded(_))));
+    }
+}
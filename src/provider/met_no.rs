@@ -0,0 +1,845 @@
+//! Provider implementation, powered by Locationforecast 2.0 of <https://api.met.no>, which requires no api key,
+//! but requires the client to identify itself. Places are resolved by open-meteo geocoding, since met.no has none.
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, EXPIRES, IF_MODIFIED_SINCE, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::open_meteo::OpenMeteo;
+use super::{
+    Condition, ConditionKind, Coordinates, Geocoder, Place, Provider, ProviderName, Weather, Wind,
+};
+use crate::cache::{Cache, CacheKey, Endpoint, Freshness};
+use crate::error::{Error, Result};
+
+static TIMEOUT_SECONDS: u64 = 5;
+static BASE_URL: &str = "https://api.met.no/";
+static LOCATIONFORECAST_PATH: &str = "weatherapi/locationforecast/2.0/";
+static PROVIDER_NAME: ProviderName = ProviderName::MetNo;
+static MET_NO_ERROR: &str = "met.no returned invalid data";
+/// Identification, which met.no terms of service require from every client.
+/// See <https://api.met.no/doc/TermsOfService>.
+static USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " github.com/khomiakmaxim/elastio_task"
+);
+
+/// Locationforecast product. The complete one additionally reports percentiles and probabilities,
+/// which are ignored, since they don't fit the normalized weather, so the compact one is the default.
+#[derive(
+    Serialize, Deserialize, strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Product {
+    #[default]
+    Compact,
+    Complete,
+}
+
+/// Concrete structure, which implements 'Provider' trait for met.no API requests.
+pub struct MetNo {
+    https_client: Client,
+    base_url: Url,
+    product: Product,
+    geocoder: OpenMeteo,
+    cache: Option<Cache>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocationForecast {
+    properties: ForecastProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastProperties {
+    timeseries: Vec<TimeStep>,
+}
+
+/// Forecast step. Steps are hourly for the next few days and six-hourly after that.
+#[derive(Debug, Deserialize)]
+struct TimeStep {
+    time: DateTime<Utc>,
+    data: StepData,
+}
+
+#[derive(Debug, Deserialize)]
+struct StepData {
+    instant: Instant,
+    next_1_hours: Option<Period>,
+    next_6_hours: Option<Period>,
+    next_12_hours: Option<Period>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Instant {
+    details: InstantDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstantDetails {
+    air_temperature: f64,
+    air_pressure_at_sea_level: Option<f64>,
+    relative_humidity: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_from_direction: Option<f64>,
+}
+
+/// Summary of the period, which follows the step.
+#[derive(Debug, Deserialize)]
+struct Period {
+    summary: Option<Summary>,
+    #[serde(default)]
+    details: PeriodDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct Summary {
+    symbol_code: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PeriodDetails {
+    precipitation_amount: Option<f64>,
+}
+
+impl TimeStep {
+    /// Precipitation and condition are taken from the shortest period, which the step reports.
+    fn to_weather(&self, place: &Place) -> Weather {
+        let details = &self.data.instant.details;
+        let periods = [
+            &self.data.next_1_hours,
+            &self.data.next_6_hours,
+            &self.data.next_12_hours,
+        ];
+        let symbol_code = periods.iter().find_map(|period| {
+            period
+                .as_ref()?
+                .summary
+                .as_ref()
+                .map(|summary| summary.symbol_code.as_str())
+        });
+        let precipitation = periods
+            .iter()
+            .find_map(|period| period.as_ref()?.details.precipitation_amount);
+
+        Weather {
+            location: place.clone(),
+            observed_at: self.time,
+            temperature: details.air_temperature,
+            feels_like: None,
+            humidity: details.relative_humidity.map(|humidity| humidity as u8),
+            pressure: details.air_pressure_at_sea_level,
+            precipitation,
+            visibility: None,
+            wind: Wind {
+                speed: details.wind_speed,
+                direction: details
+                    .wind_from_direction
+                    .map(|direction| direction as u16),
+            },
+            condition: condition(symbol_code),
+        }
+    }
+}
+
+/// Maps met.no symbol code, e.g. 'lightrainshowers_day', to the normalized condition.
+/// See <https://api.met.no/weatherapi/weathericon/2.0/documentation>.
+fn condition(symbol_code: Option<&str>) -> Condition {
+    let Some(symbol_code) = symbol_code else {
+        return Condition {
+            kind: ConditionKind::Unknown,
+            description: String::from("unknown"),
+        };
+    };
+
+    // Variants of the day, night and polar twilight share the condition.
+    let symbol = symbol_code.split('_').next().unwrap_or(symbol_code);
+    let kind = match symbol {
+        "clearsky" => ConditionKind::Clear,
+        "fair" | "partlycloudy" => ConditionKind::PartlyCloudy,
+        "cloudy" => ConditionKind::Cloudy,
+        "fog" => ConditionKind::Fog,
+        _ if symbol.contains("thunder") => ConditionKind::Thunderstorm,
+        _ if symbol.contains("sleet") => ConditionKind::Sleet,
+        _ if symbol.contains("snow") => ConditionKind::Snow,
+        _ if symbol.contains("rain") => ConditionKind::Rain,
+        _ => ConditionKind::Unknown,
+    };
+
+    Condition {
+        kind,
+        description: symbol.to_owned(),
+    }
+}
+
+/// Reads freshness of the response from its 'Expires' and 'Last-Modified' headers.
+fn freshness(headers: &HeaderMap) -> Freshness {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    Freshness {
+        expires_at: header(EXPIRES)
+            .and_then(|expires| DateTime::parse_from_rfc2822(expires).ok())
+            .and_then(|expires| u64::try_from(expires.timestamp()).ok()),
+        last_modified: header(LAST_MODIFIED).map(str::to_owned),
+    }
+}
+
+/// Step, which contains the 'instant'. The last step lasts an hour.
+fn step_at(steps: &[TimeStep], instant: DateTime<Utc>) -> Option<&TimeStep> {
+    steps.iter().enumerate().find_map(|(index, step)| {
+        let end = steps
+            .get(index + 1)
+            .map(|next| next.time)
+            .unwrap_or(step.time + chrono::Duration::hours(1));
+        (step.time <= instant && instant < end).then_some(step)
+    })
+}
+
+/// Step of the local 'date', which is the closest to its local noon.
+fn noon_step(steps: &[TimeStep], date: NaiveDate, timezone: Tz) -> Result<&TimeStep> {
+    let noon = NaiveTime::from_hms_opt(12, 0, 0)
+        .expect("Failed during time parameter initialization. Contact developers for proceeding.");
+    let local_noon = timezone
+        .from_local_datetime(&date.and_time(noon))
+        .earliest()
+        .ok_or_else(|| no_forecast(date))?;
+
+    steps
+        .iter()
+        .filter(|step| step.time.with_timezone(&timezone).date_naive() == date)
+        .min_by_key(|step| {
+            (step.time - local_noon.with_timezone(&Utc))
+                .num_minutes()
+                .abs()
+        })
+        .ok_or_else(|| no_forecast(date))
+}
+
+fn no_forecast(date: NaiveDate) -> Error {
+    Error::DateOutOfRange(format!(
+        "met.no has no forecast for {}, since it forecasts only the coming days",
+        date
+    ))
+}
+
+impl Geocoder for MetNo {
+    /// Implementation of 'Geocoder' trait method. Delegates to open-meteo geocoding.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of unknown 'query' or API limitations.
+    fn geocode(&self, query: &str) -> Result<Vec<Place>> {
+        self.geocoder.geocode(query)
+    }
+
+    /// Implementation of 'Geocoder' trait method. Neither met.no, nor open-meteo support reverse geocoding.
+    ///
+    /// # Errors:
+    ///
+    /// Always returns 'Unsupported' error, so the lookup falls back to the next provider, if any.
+    fn reverse_geocode(&self, coordinates: Coordinates) -> Result<Vec<Place>> {
+        Err(Error::Unsupported(format!(
+            "Reverse geocoding is unsupported by met-no provider, can't look up {}",
+            coordinates
+        )))
+    }
+}
+
+impl Provider for MetNo {
+    /// Implementation of 'Provider' trait method. Returns the forecast step, which contains the current moment.
+    ///
+    /// # Errors:
+    ///
+    /// Returns 'Upstream' error, if the forecast doesn't cover the current moment.
+    /// Backpropagates in case of invalid 'address', or API limitations.
+    fn get_current_weather(&self, address: &str) -> Result<Weather> {
+        let (place, _) = self.get_place(address, None)?;
+        let steps = self.get_forecast(&place)?.properties.timeseries;
+
+        step_at(&steps, Utc::now())
+            .map(|step| step.to_weather(&place))
+            .ok_or_else(|| {
+                Error::Upstream(format!(
+                    "{}: forecast doesn't cover the current moment",
+                    MET_NO_ERROR
+                ))
+            })
+    }
+
+    /// Implementation of 'Provider' trait method. Returns the step, which is the closest to the local noon
+    /// of the 'date'. Only the coming days are forecasted, there is no history.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
+    fn get_timed_weather(
+        &self,
+        address: &str,
+        date: &str,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| Error::InvalidInput(format!("invalid date '{}': {}", date, err)))?;
+
+        self.get_weather_series(address, date, date, timezone)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| no_forecast(date))
+    }
+
+    /// Implementation of 'Provider' trait method. Returns steps of the local 'date', which are six-hourly
+    /// for the distant days.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
+    fn get_hourly_weather(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let (place, timezone) = self.get_place(address, timezone)?;
+        let hourly: Vec<Weather> = self
+            .get_forecast(&place)?
+            .properties
+            .timeseries
+            .iter()
+            .filter(|step| step.time.with_timezone(&timezone).date_naive() == date)
+            .map(|step| step.to_weather(&place))
+            .collect();
+
+        if hourly.is_empty() {
+            return Err(no_forecast(date));
+        }
+
+        Ok(hourly)
+    }
+
+    /// Implementation of 'Provider' trait method. Returns the step, which contains the 'instant'.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'instant' or API limitations.
+    fn get_weather_at(
+        &self,
+        address: &str,
+        instant: DateTime<Utc>,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let (place, _) = self.get_place(address, timezone)?;
+        let steps = self.get_forecast(&place)?.properties.timeseries;
+
+        step_at(&steps, instant)
+            .map(|step| step.to_weather(&place))
+            .ok_or_else(|| {
+                Error::DateOutOfRange(format!(
+                    "met.no has no forecast for {}, since it forecasts only the coming days",
+                    instant
+                ))
+            })
+    }
+
+    /// Implementation of 'Provider' trait method. Timezone is reported by open-meteo.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or API limitations.
+    fn get_timezone(&self, address: &str) -> Result<Tz> {
+        let (_, timezone) = self.get_place(address, None)?;
+        Ok(timezone)
+    }
+
+    /// Implementation of 'Provider' trait method. The whole forecast comes in a single response,
+    /// so every date is taken from it.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or dates or API limitations.
+    fn get_weather_series(
+        &self,
+        address: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let (place, timezone) = self.get_place(address, timezone)?;
+        let steps = self.get_forecast(&place)?.properties.timeseries;
+
+        super::days_between(from, to)
+            .map(|date| Ok(noon_step(&steps, date, timezone)?.to_weather(&place)))
+            .collect()
+    }
+}
+
+impl MetNo {
+    /// Creates new entity of met.no provider.
+    pub fn new() -> MetNo {
+        MetNo {
+            geocoder: OpenMeteo::new(),
+            ..Self::with_base_url(
+                Url::parse(BASE_URL).expect(
+                    "Invalid base URL of met.no provider. Contact developers for proceeding.",
+                ),
+            )
+        }
+    }
+
+    /// Creates new entity of met.no provider, which sends requests, including geocoding ones, to the 'base_url'.
+    pub fn with_base_url(base_url: Url) -> MetNo {
+        let https_client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+            .user_agent(USER_AGENT)
+            .build()
+            .expect("Unable to build HTTPS client for met.no provider. Contact developers for proceeding.");
+
+        MetNo {
+            https_client,
+            geocoder: OpenMeteo::with_base_url(base_url.clone()),
+            base_url,
+            product: Product::default(),
+            cache: None,
+        }
+    }
+
+    /// Makes provider request the 'product' of Locationforecast.
+    pub fn with_product(mut self, product: Product) -> MetNo {
+        self.product = product;
+        self
+    }
+
+    /// Makes provider reuse responses from the 'cache' and store the new ones there.
+    /// Forecasts stay cached until they expire according to met.no.
+    pub fn with_cache(mut self, cache: Cache) -> MetNo {
+        self.geocoder = self.geocoder.with_cache(cache.clone());
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Geocodes the 'address' into the best matching place, along with its timezone. The known 'timezone'
+    /// is preferred, so it isn't requested for coordinates.
+    fn get_place(&self, address: &str, timezone: Option<Tz>) -> Result<(Place, Tz)> {
+        let mut place = self.geocode(address)?.into_iter().next().ok_or_else(|| {
+            Error::LocationNotFound(format!("No matching location found for {}", address))
+        })?;
+
+        let timezone = match (timezone, &place.timezone) {
+            (Some(timezone), _) => timezone,
+            (None, Some(timezone)) => timezone.parse().map_err(|_| {
+                Error::Upstream(format!(
+                    "open-meteo returned unknown timezone '{}'",
+                    timezone
+                ))
+            })?,
+            (None, None) => self.geocoder.get_timezone(address)?,
+        };
+        place.timezone = Some(timezone.name().to_owned());
+
+        Ok((place, timezone))
+    }
+
+    /// Requests the forecast of the 'place'. Fresh cached forecast is used instead of the request, while the stale one
+    /// is revalidated with 'If-Modified-Since' header, as met.no terms of service require.
+    fn get_forecast(&self, place: &Place) -> Result<LocationForecast> {
+        // met.no rejects coordinates with more than 4 decimals.
+        let latitude = format!("{:.4}", place.latitude);
+        let longitude = format!("{:.4}", place.longitude);
+
+        let mut url = self
+            .base_url
+            .join(LOCATIONFORECAST_PATH)?
+            .join(&self.product.to_string())?;
+        url.query_pairs_mut()
+            .append_pair("lat", &latitude)
+            .append_pair("lon", &longitude);
+
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            Endpoint::Forecast,
+            &format!("{} {},{}", self.product, latitude, longitude),
+            None,
+        );
+        if let Some(body) = self.cache.as_ref().and_then(|cache| cache.get(&cache_key)) {
+            if let Ok(response) = serde_json::from_str(&body) {
+                return Ok(response);
+            }
+        }
+
+        let stale = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get_stale(&cache_key));
+        let mut request = self.https_client.get(url.as_str());
+        if let Some((_, Some(last_modified))) = &stale {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send()?;
+        let status = response.status();
+        let mut freshness = freshness(response.headers());
+
+        let body = match stale {
+            Some((body, last_modified)) if status == StatusCode::NOT_MODIFIED => {
+                freshness.last_modified = freshness.last_modified.or(last_modified);
+                body
+            }
+            _ => {
+                let body = response.text()?;
+                if !status.is_success() {
+                    return Err(api_error(status.as_u16()));
+                }
+                body
+            }
+        };
+
+        let response = serde_json::from_str(&body)
+            .map_err(|err| Error::Upstream(format!("{}: {}", MET_NO_ERROR, err)))?;
+
+        if let Some(cache) = &self.cache {
+            // Failing to cache the response shouldn't fail the request.
+            cache.put_with_freshness(&cache_key, &body, freshness).ok();
+        }
+
+        Ok(response)
+    }
+}
+
+impl Default for MetNo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps met.no error status into the respective error category. Error responses have no structured body.
+/// See <https://api.met.no/doc/FAQ>.
+fn api_error(status: u16) -> Error {
+    match status {
+        403 => Error::Upstream(String::from(
+            "met.no refused the request, which happens, if the client isn't identified properly",
+        )),
+        422 => Error::InvalidInput(String::from(
+            "met.no can't forecast for the requested coordinates",
+        )),
+        _ => Error::from_status(status, format!("met.no responded with HTTP {}", status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cache::{tests::temporary_cache, CacheTtl};
+    use crate::mock_server::{fixture, MockServer};
+    use chrono::{Duration, Timelike};
+
+    static COMPACT_PATH: &str = "/weatherapi/locationforecast/2.0/compact";
+    static COMPLETE_PATH: &str = "/weatherapi/locationforecast/2.0/complete";
+
+    #[test]
+    fn test_condition() {
+        assert_eq!(condition(Some("clearsky_day")).kind, ConditionKind::Clear);
+        assert_eq!(
+            condition(Some("fair_polartwilight")).kind,
+            ConditionKind::PartlyCloudy
+        );
+        assert_eq!(
+            condition(Some("heavyrainandthunder")).kind,
+            ConditionKind::Thunderstorm
+        );
+        assert_eq!(
+            condition(Some("lightsleetshowers_night")).kind,
+            ConditionKind::Sleet
+        );
+        assert_eq!(
+            condition(Some("lightsnowshowers_day")).description,
+            "lightsnowshowers"
+        );
+        assert_eq!(condition(Some("rain")).kind, ConditionKind::Rain);
+        assert_eq!(condition(None).kind, ConditionKind::Unknown);
+    }
+
+    #[test]
+    fn test_api_error() {
+        assert!(matches!(api_error(403), Error::Upstream(_)));
+        assert!(matches!(api_error(400), Error::InvalidInput(_)));
+        assert!(matches!(api_error(429), Error::QuotaExceeded(_)));
+        assert!(matches!(api_error(500), Error::Upstream(_)));
+    }
+
+    /// Forecast fixture, which dates are shifted to start at 'first_date'.
+    fn forecast_from(name: &str, first_date: NaiveDate) -> String {
+        (0..10).fold(fixture(name), |forecast, offset| {
+            forecast.replace(
+                &format!("2023-04-{:02}", 7 + offset),
+                &(first_date + Duration::days(offset)).to_string(),
+            )
+        })
+    }
+
+    /// Serves the forecast, which started yesterday, so the current moment is among its hourly steps.
+    fn mock_server() -> MockServer {
+        let yesterday = Utc::now().date_naive() - Duration::days(1);
+        let server = MockServer::start();
+        server
+            .route("/v1/search", 200, &fixture("open_meteo/search.json"))
+            .route_with_query(
+                "/v1/forecast",
+                "current=",
+                200,
+                &fixture("open_meteo/current.json"),
+            )
+            .route(
+                COMPACT_PATH,
+                200,
+                &forecast_from("met_no/compact.json", yesterday),
+            )
+            .route(
+                COMPLETE_PATH,
+                200,
+                &forecast_from("met_no/complete.json", yesterday),
+            );
+        server
+    }
+
+    fn kyiv_today() -> NaiveDate {
+        Utc::now()
+            .with_timezone(&chrono_tz::Europe::Kyiv)
+            .date_naive()
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked() {
+        let server = mock_server();
+        let provider = MetNo::with_base_url(server.url());
+        let before = Utc::now();
+        let weather = provider
+            .get_current_weather("Mykolaiv, Lviv oblast, Ukraine")
+            .unwrap();
+        let after = Utc::now();
+
+        assert_eq!(weather.location.name, "Mykolaiv");
+        assert_eq!(weather.location.timezone.as_deref(), Some("Europe/Kyiv"));
+        // The hourly step, which contained the moment of the request.
+        assert_eq!(weather.observed_at.minute(), 0);
+        assert!(weather.observed_at <= after);
+        assert!(before < weather.observed_at + Duration::hours(1));
+        // Fixture's temperature grows by half a degree every UTC hour.
+        assert_eq!(
+            weather.temperature,
+            5.0 + weather.observed_at.hour() as f64 * 0.5
+        );
+        assert!(weather.precipitation.is_some());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        // Coordinates are truncated to 4 decimals.
+        assert_eq!(
+            requests[1],
+            format!("{}?lat=49.5237&lon=23.9803", COMPACT_PATH)
+        );
+        assert!(server
+            .request_header(1, "User-Agent")
+            .unwrap()
+            .starts_with("elastio_task/"));
+
+        // Outdated forecast isn't reported as the current weather.
+        server.route(
+            COMPACT_PATH,
+            200,
+            &forecast_from(
+                "met_no/compact.json",
+                Utc::now().date_naive() - Duration::days(12),
+            ),
+        );
+        assert!(matches!(
+            provider.get_current_weather("Mykolaiv"),
+            Err(Error::Upstream(_))
+        ));
+    }
+
+    #[test]
+    fn test_reverse_geocode_unsupported() {
+        let server = mock_server();
+        let error = MetNo::with_base_url(server.url())
+            .reverse_geocode("49.84,24.03".parse().unwrap())
+            .unwrap_err();
+        assert!(matches!(error, Error::Unsupported(_)));
+        assert!(error.is_retryable());
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked() {
+        let server = mock_server();
+        let provider = MetNo::with_base_url(server.url());
+        let tomorrow = Utc::now().date_naive() + Duration::days(1);
+
+        let weather = provider
+            .get_timed_weather("Mykolaiv", &tomorrow.to_string(), None)
+            .unwrap();
+        let local_time = weather.observed_at.with_timezone(&chrono_tz::Europe::Kyiv);
+        assert_eq!(local_time.date_naive(), tomorrow);
+        assert_eq!(local_time.hour(), 12);
+
+        // Distant days are six-hourly.
+        let distant = kyiv_today() + Duration::days(6);
+        let weather = provider
+            .get_timed_weather("Mykolaiv", &distant.to_string(), None)
+            .unwrap();
+        assert_eq!(weather.observed_at.hour() % 6, 0);
+
+        let result = provider.get_timed_weather(
+            "Mykolaiv",
+            &(kyiv_today() - Duration::days(3)).to_string(),
+            None,
+        );
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
+    }
+
+    #[test]
+    fn test_get_hourly_weather_mocked() {
+        let server = mock_server();
+        let today = kyiv_today();
+        let hourly = MetNo::with_base_url(server.url())
+            .get_hourly_weather("Mykolaiv", today, None)
+            .unwrap();
+
+        assert!(hourly.len() >= 23);
+        assert!(hourly.iter().all(|weather| {
+            weather
+                .observed_at
+                .with_timezone(&chrono_tz::Europe::Kyiv)
+                .date_naive()
+                == today
+        }));
+        assert_eq!(
+            hourly[0]
+                .observed_at
+                .with_timezone(&chrono_tz::Europe::Kyiv)
+                .hour(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_get_weather_at_mocked() {
+        let server = mock_server();
+        let provider = MetNo::with_base_url(server.url());
+
+        let instant = Utc::now() + Duration::hours(5);
+        let weather = provider
+            .get_weather_at("49.52,23.98", instant, None)
+            .unwrap();
+        assert!(weather.observed_at <= instant);
+        assert!(instant < weather.observed_at + Duration::hours(1));
+        // Timezone of the coordinates is reported by the current weather of open-meteo.
+        assert_eq!(weather.location.timezone.as_deref(), Some("Europe/Kyiv"));
+
+        let result = provider.get_weather_at("49.52,23.98", Utc::now() - Duration::days(2), None);
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
+    }
+
+    #[test]
+    fn test_get_weather_series_mocked() {
+        let server = mock_server();
+        let today = kyiv_today();
+        let series = MetNo::with_base_url(server.url())
+            .with_product(Product::Complete)
+            .get_weather_series("Mykolaiv, UA", today, today + Duration::days(5), None)
+            .unwrap();
+
+        assert_eq!(series.len(), 6);
+        for (offset, weather) in series.iter().enumerate() {
+            assert_eq!(
+                weather
+                    .observed_at
+                    .with_timezone(&chrono_tz::Europe::Kyiv)
+                    .date_naive(),
+                today + Duration::days(offset as i64)
+            );
+        }
+
+        // The whole series comes from a single forecast.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with(COMPLETE_PATH));
+    }
+
+    #[test]
+    fn test_get_forecast_mocked_freshness() {
+        let server = mock_server();
+        let cache = temporary_cache(CacheTtl::default());
+        let provider = MetNo::with_base_url(server.url()).with_cache(cache.clone());
+        let http_date = |time: DateTime<Utc>| time.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let last_modified = http_date(Utc::now() - Duration::minutes(30));
+        let forecast = forecast_from(
+            "met_no/compact.json",
+            Utc::now().date_naive() - Duration::days(1),
+        );
+
+        // Unexpired forecast is reused.
+        server.route_with_headers(
+            COMPACT_PATH,
+            200,
+            &forecast,
+            &[
+                ("Expires", &http_date(Utc::now() + Duration::minutes(30))),
+                ("Last-Modified", &last_modified),
+            ],
+        );
+        provider.get_current_weather("Mykolaiv").unwrap();
+        provider.get_current_weather("Mykolaiv").unwrap();
+        assert_eq!(server.requests().len(), 2);
+        cache.clear().unwrap();
+
+        // Expired forecast is revalidated, and reused, if it's not modified.
+        server.route_with_headers(
+            COMPACT_PATH,
+            200,
+            &forecast,
+            &[
+                ("Expires", &http_date(Utc::now() - Duration::minutes(1))),
+                ("Last-Modified", &last_modified),
+            ],
+        );
+        provider.get_current_weather("Mykolaiv").unwrap();
+        server.route(COMPACT_PATH, 304, "");
+        provider.get_current_weather("Mykolaiv").unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(server.request_header(3, "If-Modified-Since"), None);
+        assert_eq!(
+            server.request_header(4, "If-Modified-Since"),
+            Some(last_modified)
+        );
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn test_get_met_no_current() {
+        let weather = MetNo::new().get_current_weather("Mykolaiv, Lviv oblast, Ukraine");
+        assert!(weather.is_ok());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_get_met_no_timed_tommorow_weather() {
+        let tommorow = Utc::now() + Duration::days(1);
+        let weather = MetNo::new().get_timed_weather(
+            "Oslo, Norway",
+            &tommorow.format("%Y-%m-%d").to_string(),
+            None,
+        );
+        assert!(weather.is_ok());
+    }
+}
@@ -1,43 +1,271 @@
 //! Module for performing specific API requests. Scales for new providers.
 use std::fmt::Display;
+use std::str::FromStr;
 
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 
-/// General provider trait, used in dynamic dispatch
-pub trait Provider {
-    /// Traitmethod for retrieving weather, which is currently at the 'address', which is specified    
-    fn get_current_weather(&self, address: &str) -> anyhow::Result<Weather>;
+use crate::cache::Cache;
+use crate::error::{Error, Result};
+
+/// Resolves free-text queries into places, so the place, which weather is requested for, is known upfront.
+pub trait Geocoder {
+    /// Returns places, which match the 'query', the best match first. Coordinates are returned as the only place.
+    /// Timezone of the places is filled, if the geocoder reports it.
+    ///
+    /// # Errors:
+    /// Returns 'LocationNotFound' error, if nothing matches the 'query'.
+    fn geocode(&self, query: &str) -> Result<Vec<Place>>;
+    /// Returns places nearby the 'coordinates', the nearest first.
+    ///
+    /// # Errors:
+    /// Returns 'LocationNotFound' error, if there is no place nearby, e.g. in the ocean.
+    /// Returns 'Unsupported' error, if the provider can't look up coordinates.
+    fn reverse_geocode(&self, coordinates: Coordinates) -> Result<Vec<Place>>;
+}
+
+/// General provider trait, used in dynamic dispatch. Every provider resolves addresses, so it's a geocoder as well.
+/// The 'timezone' of the location is passed, when the caller already knows it, so providers don't request it.
+pub trait Provider: Geocoder {
+    /// Trait method for retrieving weather, which is currently at the 'address', which is specified    
+    fn get_current_weather(&self, address: &str) -> Result<Weather>;
     /// Trait method for retrieving weather, which was\will be at the 'address', which is specified and on the 'date', which is also specified    
-    fn get_timed_weather(&self, address: &str, date: &str) -> anyhow::Result<Weather>;
+    fn get_timed_weather(&self, address: &str, date: &str, timezone: Option<Tz>)
+        -> Result<Weather>;
+    /// Trait method for retrieving hour-by-hour weather at the 'address' for every hour of the 'date' in the location's local time.
+    fn get_hourly_weather(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>>;
+    /// Trait method for retrieving weather at the 'address' for the hour, which contains the 'instant'.
+    fn get_weather_at(
+        &self,
+        address: &str,
+        instant: DateTime<Utc>,
+        timezone: Option<Tz>,
+    ) -> Result<Weather>;
+    /// Trait method for resolving timezone of the 'address'.
+    fn get_timezone(&self, address: &str) -> Result<Tz>;
+    /// Trait method for retrieving weather for every day from 'from' to 'to' inclusively, ordered by date.
+    /// Providers override it to resolve the 'address' only once and to fetch several days in one request.
+    fn get_weather_series(
+        &self,
+        address: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        days_between(from, to)
+            .map(|date| {
+                self.get_timed_weather(address, &date.format("%Y-%m-%d").to_string(), timezone)
+            })
+            .collect()
+    }
 }
 
-/// Enumeration, which unifies modules outputs
-pub enum Weather { // TODO: Consider parsing output to one unified structure, making app design even less coupled
-    // OpenWeatherMap
-    FromOpenWeatherMapCurrent(open_weather_map::CurrentWeatherData),
-    FromOpenWeatherMapTimed(open_weather_map::TimedWeatherData),
-    // WeatherApi
-    FromWeatherApiCurrent(weather_api::CurrentWeatherData),
-    FromWeatherApiTimed(weather_api::TimedWeatherData),
+/// Returns every date from 'from' to 'to' inclusively.
+///
+/// # Examples
+/// ```
+/// use chrono::NaiveDate;
+/// use elastio_task::provider::days_between;
+///
+/// let from = NaiveDate::from_ymd_opt(2023, 4, 30).unwrap();
+/// let to = NaiveDate::from_ymd_opt(2023, 5, 2).unwrap();
+/// assert_eq!(days_between(from, to).count(), 3);
+/// assert_eq!(days_between(to, from).count(), 0);
+/// ```
+pub fn days_between(from: NaiveDate, to: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    (0..=(to - from).num_days()).map(move |days| from + Duration::days(days))
 }
 
-impl Display for Weather {
+/// Provider-neutral weather report. Every provider maps its own response shape into this structure,
+/// so the output doesn't depend on the configured provider. Values are in metric units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    /// Place the report belongs to, as resolved by the provider.
+    pub location: Place,
+    /// Moment the values were observed or forecasted for.
+    pub observed_at: DateTime<Utc>,
+    /// Air temperature, °C.
+    pub temperature: f64,
+    /// Apparent temperature, °C.
+    pub feels_like: Option<f64>,
+    /// Relative humidity, %.
+    pub humidity: Option<u8>,
+    /// Atmospheric pressure at sea level, hPa.
+    pub pressure: Option<f64>,
+    /// Precipitation amount, mm.
+    pub precipitation: Option<f64>,
+    /// Visibility, km.
+    pub visibility: Option<f64>,
+    pub wind: Wind,
+    pub condition: Condition,
+}
+
+/// Geographical place, which weather report is related to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub region: Option<String>,
+    pub country: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// IANA timezone identifier, e.g. 'Europe/Kyiv'.
+    pub timezone: Option<String>,
+}
+
+impl Place {
+    /// Creates place of the 'coordinates', which name is the coordinates themselves.
+    pub fn at(coordinates: Coordinates) -> Place {
+        Place {
+            name: coordinates.to_string(),
+            region: None,
+            country: None,
+            latitude: coordinates.latitude,
+            longitude: coordinates.longitude,
+            timezone: None,
+        }
+    }
+
+    /// Creates place of the 'coordinates', which is named after the 'nearby' place.
+    pub fn named_after(coordinates: Coordinates, nearby: Place) -> Place {
+        Place {
+            latitude: coordinates.latitude,
+            longitude: coordinates.longitude,
+            ..nearby
+        }
+    }
+
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
+impl Display for Place {
+    /// Displays place along with its region, country and coordinates, e.g. 'Mykolaiv, Lviv Oblast, UA (49.52, 23.98)'.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Weather::FromOpenWeatherMapCurrent(data) => {
-                write!(f, "{}", serde_json::to_string_pretty(data).unwrap())
-            }
-            Weather::FromOpenWeatherMapTimed(data) => {
-                write!(f, "{}", serde_json::to_string_pretty(data).unwrap())
-            }
-            Weather::FromWeatherApiCurrent(data) => {
-                write!(f, "{}", serde_json::to_string_pretty(data).unwrap())
-            }
-            Weather::FromWeatherApiTimed(data) => {
-                write!(f, "{}", serde_json::to_string_pretty(data).unwrap())
-            }
+        let name = [
+            Some(self.name.as_str()),
+            self.region.as_deref(),
+            self.country.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+        write!(f, "{} ({:.2}, {:.2})", name, self.latitude, self.longitude)
+    }
+}
+
+/// Geographical coordinates, which might be passed instead of the address as 'latitude,longitude', e.g. '49.84,24.03'.
+/// Providers request weather at the coordinates as is, without geocoding them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Creates coordinates, validating their ranges.
+    ///
+    /// # Errors:
+    /// Returns 'InvalidInput' error, if latitude isn't within [-90, 90] or longitude isn't within [-180, 180].
+    pub fn new(latitude: f64, longitude: f64) -> Result<Coordinates> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(Error::InvalidInput(format!(
+                "Coordinates {},{} are out of range: latitude should be within [-90, 90], longitude within [-180, 180]",
+                latitude, longitude
+            )));
         }
+
+        Ok(Coordinates {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+impl FromStr for Coordinates {
+    type Err = Error;
+
+    /// Parses coordinates in the 'latitude,longitude' format.
+    ///
+    /// # Examples
+    /// ```
+    /// use elastio_task::provider::Coordinates;
+    ///
+    /// let coordinates: Coordinates = "49.84, 24.03".parse().unwrap();
+    /// assert_eq!(coordinates.to_string(), "49.84,24.03");
+    /// assert!("Lviv, Ukraine".parse::<Coordinates>().is_err());
+    /// ```
+    fn from_str(input: &str) -> Result<Self> {
+        let invalid = || {
+            Error::InvalidInput(format!(
+                "Unable to parse coordinates '{}': expected 'latitude,longitude', e.g. 49.84,24.03",
+                input
+            ))
+        };
+        let (latitude, longitude) = input.split_once(',').ok_or_else(invalid)?;
+        let latitude = latitude.trim().parse().map_err(|_| invalid())?;
+        let longitude = longitude.trim().parse().map_err(|_| invalid())?;
+
+        Coordinates::new(latitude, longitude)
+    }
+}
+
+impl Display for Coordinates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.latitude, self.longitude)
+    }
+}
+
+/// Wind parameters of the weather report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Wind {
+    /// Wind speed, m/s.
+    pub speed: Option<f64>,
+    /// Meteorological wind direction, degrees.
+    pub direction: Option<u16>,
+}
+
+/// Weather condition, normalized to a set of kinds, shared by all providers, along with provider's description.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub kind: ConditionKind,
+    pub description: String,
+}
+
+/// Normalized set of weather conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ConditionKind {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Sleet,
+    Snow,
+    Thunderstorm,
+    Unknown,
+}
+
+impl Display for Weather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", json)
     }
 }
 
@@ -46,6 +274,7 @@ impl Display for Weather {
     Clone,
     Copy,
     clap::Subcommand,
+    clap::ValueEnum,
     Serialize,
     Deserialize,
     strum_macros::Display,
@@ -53,31 +282,57 @@ impl Display for Weather {
     Hash,
     PartialEq,
     Eq,
+    Default,
 )]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 /// Enumeration which represents a set of possible providers and which also provides functionality for creating dynamically dispatched providers.
 pub enum ProviderName {
+    #[default]
     OpenWeatherMap,
     WeatherApi,
-}
-
-impl Default for ProviderName {
-    fn default() -> Self {
-        ProviderName::OpenWeatherMap
-    }
+    OpenMeteo,
+    MetNo,
 }
 
 impl ProviderName {
     /// Returns a dynamically dispatched instance of a provider that implements the `Provider` trait, based on the `ProviderName` variant and the respective `api_key`.
-    pub fn get_provider_instance(&self, api_key: String) -> Box<dyn Provider> {
+    /// Provider reuses responses from the `cache`, if it's specified. Met-no provider requests the `met_no_product`,
+    /// while the others ignore it.
+    pub fn get_provider_instance(
+        &self,
+        api_key: String,
+        cache: Option<Cache>,
+        met_no_product: met_no::Product,
+    ) -> Box<dyn Provider> {
         match *self {
-            ProviderName::OpenWeatherMap => {
-                Box::new(open_weather_map::OpenWeatherMap::new(api_key))
-            }
-            ProviderName::WeatherApi => Box::new(weather_api::WeatherApi::new(api_key)),
+            ProviderName::OpenWeatherMap => with_optional_cache(
+                open_weather_map::OpenWeatherMap::new(api_key),
+                cache,
+                open_weather_map::OpenWeatherMap::with_cache,
+            ),
+            ProviderName::WeatherApi => with_optional_cache(
+                weather_api::WeatherApi::new(api_key),
+                cache,
+                weather_api::WeatherApi::with_cache,
+            ),
+            ProviderName::OpenMeteo => with_optional_cache(
+                open_meteo::OpenMeteo::new(),
+                cache,
+                open_meteo::OpenMeteo::with_cache,
+            ),
+            ProviderName::MetNo => with_optional_cache(
+                met_no::MetNo::new().with_product(met_no_product),
+                cache,
+                met_no::MetNo::with_cache,
+            ),
         }
     }
 
+    /// Returns whether the provider needs an api key. Keyless providers ignore the key they are given.
+    pub fn requires_api_key(&self) -> bool {
+        !matches!(self, ProviderName::OpenMeteo | ProviderName::MetNo)
+    }
+
     /// Returns a pretty name of encoded 'ProviderName' in .env file.
     ///
     /// # Examples
@@ -93,5 +348,20 @@ impl ProviderName {
     }
 }
 
+/// Boxes the 'provider', which reuses responses from the 'cache' attached by 'with_cache', if the cache is specified.
+fn with_optional_cache<P: Provider + 'static>(
+    provider: P,
+    cache: Option<Cache>,
+    with_cache: fn(P, Cache) -> P,
+) -> Box<dyn Provider> {
+    match cache {
+        Some(cache) => Box::new(with_cache(provider, cache)),
+        None => Box::new(provider),
+    }
+}
+
+pub mod fallback;
+pub mod met_no;
+pub mod open_meteo;
 pub mod open_weather_map;
 pub mod weather_api;
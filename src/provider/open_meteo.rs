@@ -0,0 +1,936 @@
+//! Provider implementation, powered by <https://open-meteo.com>, which requires no api key.
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use reqwest::blocking::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use url::Url;
+
+use super::{
+    Condition, ConditionKind, Coordinates, Geocoder, Place, Provider, ProviderName, Weather, Wind,
+};
+use crate::cache::{Cache, CacheKey, Endpoint};
+use crate::error::{Error, Result};
+
+static TIMEOUT_SECONDS: u64 = 5;
+static FORECAST_BASE_URL: &str = "https://api.open-meteo.com/";
+static ARCHIVE_BASE_URL: &str = "https://archive-api.open-meteo.com/";
+static GEOCODING_BASE_URL: &str = "https://geocoding-api.open-meteo.com/";
+static PROVIDER_NAME: ProviderName = ProviderName::OpenMeteo;
+static OPEN_METEO_ERROR: &str = "open-meteo returned invalid data";
+/// Maximum number of geocoding candidates, before they are narrowed by the rest of the address.
+static GEOCODING_LIMIT: u8 = 10;
+/// Days, which the archive lags behind today. More recent days are requested from the forecast, which keeps recent past.
+static ARCHIVE_DELAY_DAYS: i64 = 5;
+/// Variables of the current weather and the hourly forecast.
+static FORECAST_VARIABLES: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,pressure_msl,precipitation,visibility,weather_code,wind_speed_10m,wind_direction_10m";
+/// Variables of the archive, which has no visibility.
+static ARCHIVE_VARIABLES: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,pressure_msl,precipitation,weather_code,wind_speed_10m,wind_direction_10m";
+
+/// Concrete structure, which implements 'Provider' trait for open-meteo API requests.
+pub struct OpenMeteo {
+    https_client: Client,
+    forecast_url: Url,
+    archive_url: Url,
+    geocoding_url: Url,
+    cache: Option<Cache>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    /// Absent, if nothing matches the query.
+    #[serde(default)]
+    results: Vec<GeocodedPlace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodedPlace {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: Option<String>,
+    country: Option<String>,
+    admin1: Option<String>,
+    timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentWeatherData {
+    timezone: String,
+    current: CurrentInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentInfo {
+    /// Local time of the location.
+    time: String,
+    temperature_2m: f64,
+    apparent_temperature: Option<f64>,
+    relative_humidity_2m: Option<f64>,
+    pressure_msl: Option<f64>,
+    precipitation: Option<f64>,
+    visibility: Option<f64>,
+    weather_code: Option<u8>,
+    wind_speed_10m: Option<f64>,
+    wind_direction_10m: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourlyWeatherData {
+    timezone: String,
+    hourly: HourlyInfo,
+}
+
+/// Hourly variables, one array per variable. Values are missing for hours, which aren't known yet.
+#[derive(Debug, Deserialize)]
+struct HourlyInfo {
+    /// Local times of the location.
+    time: Vec<String>,
+    temperature_2m: Vec<Option<f64>>,
+    apparent_temperature: Vec<Option<f64>>,
+    relative_humidity_2m: Vec<Option<f64>>,
+    pressure_msl: Vec<Option<f64>>,
+    precipitation: Vec<Option<f64>>,
+    #[serde(default)]
+    visibility: Vec<Option<f64>>,
+    weather_code: Vec<Option<u8>>,
+    wind_speed_10m: Vec<Option<f64>>,
+    wind_direction_10m: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    reason: String,
+}
+
+/// Weather of a single hour, along with its local time.
+struct Hour {
+    local_time: NaiveDateTime,
+    weather: Weather,
+}
+
+impl From<GeocodedPlace> for Place {
+    fn from(place: GeocodedPlace) -> Self {
+        Place {
+            name: place.name,
+            region: place.admin1,
+            country: place.country.or(place.country_code),
+            latitude: place.latitude,
+            longitude: place.longitude,
+            timezone: place.timezone,
+        }
+    }
+}
+
+impl GeocodedPlace {
+    /// Returns whether the place matches the 'qualifier', which is a part of the address after the name,
+    /// e.g. region or country.
+    fn matches(&self, qualifier: &str) -> bool {
+        let code_matches = self
+            .country_code
+            .as_deref()
+            .is_some_and(|code| code.eq_ignore_ascii_case(qualifier));
+        let name_matches = [self.admin1.as_deref(), self.country.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::to_lowercase)
+            .any(|name| name.contains(qualifier) || qualifier.contains(&name));
+
+        code_matches || name_matches
+    }
+}
+
+impl CurrentWeatherData {
+    fn into_weather(self, place: &Place) -> Result<Weather> {
+        let timezone = parse_timezone(&self.timezone)?;
+        let current = self.current;
+
+        Ok(Weather {
+            location: located(place, &self.timezone),
+            observed_at: to_utc(&parse_local_time(&current.time)?, timezone),
+            temperature: current.temperature_2m,
+            feels_like: current.apparent_temperature,
+            humidity: current.relative_humidity_2m.map(|humidity| humidity as u8),
+            pressure: current.pressure_msl,
+            precipitation: current.precipitation,
+            visibility: current.visibility.map(meters_to_kilometers),
+            wind: Wind {
+                speed: current.wind_speed_10m,
+                direction: current.wind_direction_10m.map(|direction| direction as u16),
+            },
+            condition: condition(current.weather_code),
+        })
+    }
+}
+
+impl HourlyWeatherData {
+    /// Splits hourly arrays into hours, leaving out hours without temperature.
+    fn into_hours(self, place: &Place) -> Result<Vec<Hour>> {
+        let timezone = parse_timezone(&self.timezone)?;
+        let location = located(place, &self.timezone);
+        let hourly = self.hourly;
+        let value = |values: &[Option<f64>], index: usize| values.get(index).copied().flatten();
+
+        let mut hours = Vec::new();
+        for (index, time) in hourly.time.iter().enumerate() {
+            let Some(temperature) = value(&hourly.temperature_2m, index) else {
+                continue;
+            };
+            let local_time = parse_local_time(time)?;
+
+            hours.push(Hour {
+                local_time,
+                weather: Weather {
+                    location: location.clone(),
+                    observed_at: to_utc(&local_time, timezone),
+                    temperature,
+                    feels_like: value(&hourly.apparent_temperature, index),
+                    humidity: value(&hourly.relative_humidity_2m, index)
+                        .map(|humidity| humidity as u8),
+                    pressure: value(&hourly.pressure_msl, index),
+                    precipitation: value(&hourly.precipitation, index),
+                    visibility: value(&hourly.visibility, index).map(meters_to_kilometers),
+                    wind: Wind {
+                        speed: value(&hourly.wind_speed_10m, index),
+                        direction: value(&hourly.wind_direction_10m, index)
+                            .map(|direction| direction as u16),
+                    },
+                    condition: condition(hourly.weather_code.get(index).copied().flatten()),
+                },
+            });
+        }
+
+        Ok(hours)
+    }
+}
+
+/// Place with the 'timezone', reported by the weather response.
+fn located(place: &Place, timezone: &str) -> Place {
+    Place {
+        timezone: Some(timezone.to_owned()),
+        ..place.clone()
+    }
+}
+
+fn parse_timezone(timezone: &str) -> Result<Tz> {
+    timezone.parse().map_err(|_| {
+        Error::Upstream(format!(
+            "open-meteo returned unknown timezone '{}'",
+            timezone
+        ))
+    })
+}
+
+fn parse_local_time(time: &str) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")
+        .map_err(|err| Error::Upstream(format!("{}: {}", OPEN_METEO_ERROR, err)))
+}
+
+/// Converts local time of the 'timezone' into UTC. Local times, which are skipped by DST, are shifted by an hour.
+fn to_utc(local_time: &NaiveDateTime, timezone: Tz) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(local_time)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(*local_time + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(local_time))
+}
+
+fn meters_to_kilometers(meters: f64) -> f64 {
+    meters / 1000.0
+}
+
+/// Maps WMO weather code, which open-meteo reports, to the normalized condition.
+/// See <https://open-meteo.com/en/docs#weathervariables>.
+fn condition(code: Option<u8>) -> Condition {
+    let (kind, description) = match code {
+        Some(0) => (ConditionKind::Clear, "clear sky"),
+        Some(1) => (ConditionKind::Clear, "mainly clear"),
+        Some(2) => (ConditionKind::PartlyCloudy, "partly cloudy"),
+        Some(3) => (ConditionKind::Cloudy, "overcast"),
+        Some(45) => (ConditionKind::Fog, "fog"),
+        Some(48) => (ConditionKind::Fog, "depositing rime fog"),
+        Some(51 | 53 | 55) => (ConditionKind::Drizzle, "drizzle"),
+        Some(56 | 57) => (ConditionKind::Sleet, "freezing drizzle"),
+        Some(61 | 63 | 65) => (ConditionKind::Rain, "rain"),
+        Some(66 | 67) => (ConditionKind::Sleet, "freezing rain"),
+        Some(71 | 73 | 75) => (ConditionKind::Snow, "snow fall"),
+        Some(77) => (ConditionKind::Snow, "snow grains"),
+        Some(80..=82) => (ConditionKind::Rain, "rain showers"),
+        Some(85 | 86) => (ConditionKind::Snow, "snow showers"),
+        Some(95) => (ConditionKind::Thunderstorm, "thunderstorm"),
+        Some(96 | 99) => (ConditionKind::Thunderstorm, "thunderstorm with hail"),
+        _ => (ConditionKind::Unknown, "unknown"),
+    };
+
+    Condition {
+        kind,
+        description: description.to_owned(),
+    }
+}
+
+impl Geocoder for OpenMeteo {
+    /// Implementation of 'Geocoder' trait method. See <https://open-meteo.com/en/docs/geocoding-api>.
+    /// Only the name, which precedes the first comma, is searched, while the rest of the address, e.g. region
+    /// or country, narrows the found places.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of unknown 'query' or API limitations.
+    fn geocode(&self, query: &str) -> Result<Vec<Place>> {
+        if let Ok(coordinates) = query.parse::<Coordinates>() {
+            return Ok(vec![Place::at(coordinates)]);
+        }
+
+        let mut parts = query.split(',').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let qualifiers: Vec<String> = parts
+            .filter(|part| !part.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+
+        let mut url = self.geocoding_url.join("v1/search")?;
+        url.query_pairs_mut()
+            .append_pair("name", name)
+            .append_pair("count", &GEOCODING_LIMIT.to_string())
+            .append_pair("language", "en")
+            .append_pair("format", "json");
+
+        let cache_key = CacheKey::new(PROVIDER_NAME, Endpoint::Geocoding, name, None);
+        let response = self.get_response::<SearchResponse>(&url, &cache_key)?;
+
+        let places: Vec<Place> = response
+            .results
+            .into_iter()
+            .filter(|place| qualifiers.iter().all(|qualifier| place.matches(qualifier)))
+            .map(Place::from)
+            .collect();
+
+        if places.is_empty() {
+            return Err(Error::LocationNotFound(format!(
+                "No matching location found for {}",
+                query
+            )));
+        }
+
+        Ok(places)
+    }
+
+    /// Implementation of 'Geocoder' trait method. Open-meteo has no reverse geocoding.
+    ///
+    /// # Errors:
+    ///
+    /// Always returns 'Unsupported' error, so the lookup falls back to the next provider, if any.
+    fn reverse_geocode(&self, coordinates: Coordinates) -> Result<Vec<Place>> {
+        Err(Error::Unsupported(format!(
+            "Reverse geocoding is unsupported by open-meteo provider, can't look up {}",
+            coordinates
+        )))
+    }
+}
+
+impl Provider for OpenMeteo {
+    /// Implementation of 'Provider' trait method. Returns weather, mapped into the normalized structure.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address', or API limitations.
+    fn get_current_weather(&self, address: &str) -> Result<Weather> {
+        let place = self.get_place(address)?;
+        self.get_current_weather_data(&place)?.into_weather(&place)
+    }
+
+    /// Implementation of 'Provider' trait method. Returns weather at the local noon of the 'date'.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
+    fn get_timed_weather(
+        &self,
+        address: &str,
+        date: &str,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| Error::InvalidInput(format!("invalid date '{}': {}", date, err)))?;
+
+        self.get_weather_series(address, date, date, timezone)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| no_data(date))
+    }
+
+    /// Implementation of 'Provider' trait method. Hours are already in the location's local time, so the timezone
+    /// isn't needed.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
+    fn get_hourly_weather(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        _timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let place = self.get_place(address)?;
+        let hourly: Vec<Weather> = self
+            .get_hours(&place, date, date)?
+            .into_iter()
+            .filter(|hour| hour.local_time.date() == date)
+            .map(|hour| hour.weather)
+            .collect();
+
+        if hourly.is_empty() {
+            return Err(no_data(date));
+        }
+
+        Ok(hourly)
+    }
+
+    /// Implementation of 'Provider' trait method. Returns the hour, which contains the 'instant'.
+    /// Days around the UTC date of the 'instant' are requested, so the local date is covered in any timezone.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'instant' or API limitations.
+    fn get_weather_at(
+        &self,
+        address: &str,
+        instant: DateTime<Utc>,
+        _timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let place = self.get_place(address)?;
+        let date = instant.date_naive();
+        let day = chrono::Duration::days(1);
+
+        self.get_hours(&place, date - day, date + day)?
+            .into_iter()
+            .map(|hour| hour.weather)
+            .find(|weather| {
+                weather.observed_at <= instant
+                    && instant < weather.observed_at + chrono::Duration::hours(1)
+            })
+            .ok_or_else(|| {
+                Error::DateOutOfRange(format!("open-meteo returned no data for {}", instant))
+            })
+    }
+
+    /// Implementation of 'Provider' trait method. Timezone is reported by the geocoding, or by the current weather
+    /// for coordinates.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or API limitations.
+    fn get_timezone(&self, address: &str) -> Result<Tz> {
+        let place = self.get_place(address)?;
+        match place.timezone {
+            Some(ref timezone) => parse_timezone(timezone),
+            None => parse_timezone(&self.get_current_weather_data(&place)?.timezone),
+        }
+    }
+
+    /// Implementation of 'Provider' trait method. Resolves the 'address' once and requests hourly weather
+    /// of the whole range at once, reporting every date at its local noon. Hours are already in the location's
+    /// local time, so the timezone isn't needed.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or dates or API limitations.
+    fn get_weather_series(
+        &self,
+        address: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        _timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let place = self.get_place(address)?;
+        let hours = self.get_hours(&place, from, to)?;
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).expect(
+            "Failed during time parameter initialization. Contact developers for proceeding.",
+        );
+
+        super::days_between(from, to)
+            .map(|date| {
+                hours
+                    .iter()
+                    .find(|hour| hour.local_time == date.and_time(noon))
+                    .map(|hour| hour.weather.clone())
+                    .ok_or_else(|| no_data(date))
+            })
+            .collect()
+    }
+}
+
+impl OpenMeteo {
+    /// Creates new entity of open-meteo provider.
+    pub fn new() -> OpenMeteo {
+        let parse = |url| {
+            Url::parse(url).expect(
+                "Invalid base URL of open-meteo provider. Contact developers for proceeding.",
+            )
+        };
+
+        OpenMeteo {
+            forecast_url: parse(FORECAST_BASE_URL),
+            archive_url: parse(ARCHIVE_BASE_URL),
+            geocoding_url: parse(GEOCODING_BASE_URL),
+            ..Self::with_base_url(parse(FORECAST_BASE_URL))
+        }
+    }
+
+    /// Creates new entity of open-meteo provider, which sends requests of all its APIs to the 'base_url',
+    /// e.g. to a self-hosted instance or a local stand-in.
+    pub fn with_base_url(base_url: Url) -> OpenMeteo {
+        let https_client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+            .build()
+            .expect("Unable to build HTTPS client for open-meteo provider. Contact developers for proceeding.");
+
+        OpenMeteo {
+            https_client,
+            forecast_url: base_url.clone(),
+            archive_url: base_url.clone(),
+            geocoding_url: base_url,
+            cache: None,
+        }
+    }
+
+    /// Makes provider reuse responses from the 'cache' and store the new ones there.
+    pub fn with_cache(mut self, cache: Cache) -> OpenMeteo {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Performs request and parses successful response, or maps the failed one into the respective error.
+    /// Fresh cached response under 'cache_key' is used instead of the request, if there is one.
+    fn get_response<T: DeserializeOwned>(&self, url: &Url, cache_key: &CacheKey) -> Result<T> {
+        if let Some(body) = self.cache.as_ref().and_then(|cache| cache.get(cache_key)) {
+            if let Ok(response) = serde_json::from_str(&body) {
+                return Ok(response);
+            }
+        }
+
+        let response = self.https_client.get(url.as_str()).send()?;
+        let status = response.status();
+        let body = response.text()?;
+
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), &body));
+        }
+
+        let response = serde_json::from_str(&body)
+            .map_err(|err| Error::Upstream(format!("{}: {}", OPEN_METEO_ERROR, err)))?;
+
+        if let Some(cache) = &self.cache {
+            // Failing to cache the response shouldn't fail the request.
+            cache.put(cache_key, &body).ok();
+        }
+
+        Ok(response)
+    }
+
+    /// Geocodes the 'address' into the best matching place. Coordinates are kept as is, since open-meteo
+    /// can't name them.
+    fn get_place(&self, address: &str) -> Result<Place> {
+        self.geocode(address)?.into_iter().next().ok_or_else(|| {
+            Error::LocationNotFound(format!("No matching location found for {}", address))
+        })
+    }
+
+    fn get_current_weather_data(&self, place: &Place) -> Result<CurrentWeatherData> {
+        let mut url = self.forecast_url.join("v1/forecast")?;
+        url.query_pairs_mut()
+            .append_pair("latitude", &place.latitude.to_string())
+            .append_pair("longitude", &place.longitude.to_string())
+            .append_pair("current", FORECAST_VARIABLES)
+            .append_pair("timezone", "auto")
+            .append_pair("wind_speed_unit", "ms");
+
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            Endpoint::Current,
+            &place.coordinates().to_string(),
+            None,
+        );
+        self.get_response::<CurrentWeatherData>(&url, &cache_key)
+    }
+
+    /// Requests hours of every date from 'from' to 'to' in the location's local time. Dates, which the archive
+    /// already has, are requested from it, while the recent and future ones are requested from the forecast.
+    fn get_hours(&self, place: &Place, from: NaiveDate, to: NaiveDate) -> Result<Vec<Hour>> {
+        let last_archived = Utc::now().date_naive() - chrono::Duration::days(ARCHIVE_DELAY_DAYS);
+        let mut hours = Vec::new();
+
+        if from <= last_archived {
+            let response = self.get_hourly_response(
+                &self.archive_url,
+                "v1/archive",
+                ARCHIVE_VARIABLES,
+                Endpoint::History,
+                place,
+                (from, to.min(last_archived)),
+            )?;
+            hours.extend(response.into_hours(place)?);
+        }
+        if to > last_archived {
+            let response = self.get_hourly_response(
+                &self.forecast_url,
+                "v1/forecast",
+                FORECAST_VARIABLES,
+                Endpoint::Forecast,
+                place,
+                (from.max(last_archived + chrono::Duration::days(1)), to),
+            )?;
+            hours.extend(response.into_hours(place)?);
+        }
+
+        Ok(hours)
+    }
+
+    fn get_hourly_response(
+        &self,
+        base_url: &Url,
+        path: &str,
+        variables: &str,
+        endpoint: Endpoint,
+        place: &Place,
+        (from, to): (NaiveDate, NaiveDate),
+    ) -> Result<HourlyWeatherData> {
+        let mut url = base_url.join(path)?;
+        url.query_pairs_mut()
+            .append_pair("latitude", &place.latitude.to_string())
+            .append_pair("longitude", &place.longitude.to_string())
+            .append_pair("hourly", variables)
+            .append_pair("start_date", &from.to_string())
+            .append_pair("end_date", &to.to_string())
+            .append_pair("timezone", "auto")
+            .append_pair("wind_speed_unit", "ms");
+
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            endpoint,
+            &place.coordinates().to_string(),
+            Some(&format!("{}..{}", from, to)),
+        );
+        self.get_response::<HourlyWeatherData>(&url, &cache_key)
+    }
+}
+
+impl Default for OpenMeteo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn no_data(date: NaiveDate) -> Error {
+    Error::DateOutOfRange(format!("open-meteo returned no data for {}", date))
+}
+
+/// Maps open-meteo error response into the respective error category.
+/// See <https://open-meteo.com/en/docs#errors>.
+fn api_error(status: u16, body: &str) -> Error {
+    let reason = match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(response) => response.reason,
+        Err(_) => {
+            return Error::from_status(status, format!("open-meteo responded with HTTP {}", status))
+        }
+    };
+
+    match status {
+        400 if reason.contains("date") => Error::DateOutOfRange(reason),
+        _ => Error::from_status(status, reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cache::{tests::temporary_cache, CacheTtl};
+    use crate::mock_server::{fixture, MockServer};
+    use chrono::Duration;
+
+    #[test]
+    fn test_condition() {
+        assert_eq!(condition(Some(0)).kind, ConditionKind::Clear);
+        assert_eq!(condition(Some(3)).kind, ConditionKind::Cloudy);
+        assert_eq!(condition(Some(66)).kind, ConditionKind::Sleet);
+        assert_eq!(condition(Some(81)).description, "rain showers");
+        assert_eq!(condition(None).kind, ConditionKind::Unknown);
+    }
+
+    #[test]
+    fn test_api_error() {
+        let error = api_error(400, &fixture("open_meteo/error_400.json"));
+        assert!(matches!(error, Error::DateOutOfRange(_)));
+
+        let error = api_error(
+            400,
+            r#"{"error": true, "reason": "Latitude must be in range of -90 to 90°."}"#,
+        );
+        assert!(matches!(error, Error::InvalidInput(_)));
+
+        let error = api_error(429, "Too Many Requests");
+        assert!(matches!(error, Error::QuotaExceeded(_)));
+    }
+
+    fn mock_server() -> MockServer {
+        let server = MockServer::start();
+        server
+            .route("/v1/search", 200, &fixture("open_meteo/search.json"))
+            .route("/v1/forecast", 200, &fixture("open_meteo/forecast.json"))
+            .route_with_query(
+                "/v1/forecast",
+                "current=",
+                200,
+                &fixture("open_meteo/current.json"),
+            )
+            .route("/v1/archive", 200, &fixture("open_meteo/archive.json"));
+        server
+    }
+
+    /// Forecast fixture, which dates are shifted to start today.
+    fn forecast_from_today() -> (NaiveDate, String) {
+        let today = Utc::now().date_naive();
+        let forecast = fixture("open_meteo/forecast.json")
+            .replace("2023-04-07", &today.to_string())
+            .replace("2023-04-08", &(today + Duration::days(1)).to_string())
+            .replace("2023-04-09", &(today + Duration::days(2)).to_string());
+        (today, forecast)
+    }
+
+    #[test]
+    fn test_geocode_mocked() {
+        let server = mock_server();
+        let provider = OpenMeteo::with_base_url(server.url());
+
+        let places = provider.geocode("Mykolaiv").unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].timezone.as_deref(), Some("Europe/Kyiv"));
+
+        // Only the name is searched, while the region narrows the places.
+        let places = provider.geocode("Mykolaiv, Lviv oblast, UA").unwrap();
+        assert_eq!(places.len(), 1);
+        assert_eq!(
+            places[0].to_string(),
+            "Mykolaiv, Lviv Oblast, Ukraine (49.52, 23.98)"
+        );
+        assert!(server.requests()[1].contains("name=Mykolaiv&count=10"));
+
+        assert!(matches!(
+            provider.geocode("Mykolaiv, Poland"),
+            Err(Error::LocationNotFound(_))
+        ));
+        server.route("/v1/search", 200, r#"{"generationtime_ms": 0.5}"#);
+        assert!(matches!(
+            provider.geocode("SO INVALID ADDRESS"),
+            Err(Error::LocationNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_reverse_geocode_unsupported() {
+        let server = mock_server();
+        let error = OpenMeteo::with_base_url(server.url())
+            .reverse_geocode("49.84,24.03".parse().unwrap())
+            .unwrap_err();
+        assert!(matches!(error, Error::Unsupported(_)));
+        assert!(error.is_retryable());
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked() {
+        let server = mock_server();
+        let weather = OpenMeteo::with_base_url(server.url())
+            .get_current_weather("Mykolaiv, Lviv oblast, Ukraine")
+            .unwrap();
+
+        assert_eq!(weather.location.name, "Mykolaiv");
+        assert_eq!(weather.temperature, 9.7);
+        assert_eq!(weather.humidity, Some(57));
+        assert_eq!(weather.wind.direction, Some(245));
+        assert_eq!(weather.condition.kind, ConditionKind::PartlyCloudy);
+        // 15:15 in Kyiv is 12:15 UTC.
+        assert_eq!(weather.observed_at.timestamp(), 1680869700);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("latitude=49.52373&longitude=23.98027"));
+        assert!(requests[1].contains("timezone=auto"));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_coordinates() {
+        let server = mock_server();
+        let weather = OpenMeteo::with_base_url(server.url())
+            .get_current_weather("49.84,24.03")
+            .unwrap();
+
+        // Coordinates aren't geocoded, while the timezone comes from the response.
+        assert_eq!(weather.location.name, "49.84,24.03");
+        assert_eq!(weather.location.timezone.as_deref(), Some("Europe/Kyiv"));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_archive() {
+        let server = mock_server();
+        let provider = OpenMeteo::with_base_url(server.url());
+
+        let weather = provider
+            .get_timed_weather("Mykolaiv, Lviv oblast", "2023-04-06", None)
+            .unwrap();
+        // Local noon of the second day of the archive.
+        assert_eq!(weather.temperature, 9.7);
+        assert_eq!(weather.visibility, None);
+
+        let request = server.requests().last().unwrap().clone();
+        assert!(request.starts_with("/v1/archive"));
+        assert!(request.contains("start_date=2023-04-06&end_date=2023-04-06"));
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_forecast() {
+        let (today, forecast) = forecast_from_today();
+        let server = mock_server();
+        server.route("/v1/forecast", 200, &forecast);
+
+        let weather = OpenMeteo::with_base_url(server.url())
+            .get_timed_weather("Mykolaiv", &(today + Duration::days(1)).to_string(), None)
+            .unwrap();
+        assert_eq!(weather.temperature, 9.7);
+        assert_eq!(weather.visibility, Some(24.14));
+        assert!(server
+            .requests()
+            .last()
+            .unwrap()
+            .starts_with("/v1/forecast"));
+
+        server.route("/v1/forecast", 400, &fixture("open_meteo/error_400.json"));
+        let result = OpenMeteo::with_base_url(server.url()).get_timed_weather(
+            "Mykolaiv",
+            &(today + Duration::days(30)).to_string(),
+            None,
+        );
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
+    }
+
+    #[test]
+    fn test_get_hourly_weather_mocked() {
+        let server = mock_server();
+        let provider = OpenMeteo::with_base_url(server.url());
+        let date = NaiveDate::from_ymd_opt(2023, 4, 6).unwrap();
+
+        // The latest hours aren't archived yet.
+        let hourly = provider.get_hourly_weather("Mykolaiv", date, None).unwrap();
+        assert_eq!(hourly.len(), 22);
+        // Midnight in Kyiv is 21:00 UTC of the previous day.
+        assert_eq!(hourly[0].observed_at.timestamp(), 1680728400);
+        assert_eq!(hourly[15].precipitation, Some(0.6));
+        assert_eq!(hourly[15].condition.kind, ConditionKind::Rain);
+    }
+
+    #[test]
+    fn test_get_weather_at_mocked() {
+        let server = mock_server();
+        let provider = OpenMeteo::with_base_url(server.url());
+
+        // 15:20 in Kyiv on 2023-04-06.
+        let instant = Utc.timestamp_opt(1680728400 + 15 * 3600 + 1200, 0).unwrap();
+        let weather = provider.get_weather_at("Mykolaiv", instant, None).unwrap();
+        assert_eq!(weather.observed_at.timestamp(), 1680728400 + 15 * 3600);
+        assert!(server
+            .requests()
+            .last()
+            .unwrap()
+            .contains("start_date=2023-04-05&end_date=2023-04-07"));
+
+        assert_eq!(
+            provider.get_timezone("Mykolaiv").unwrap().name(),
+            "Europe/Kyiv"
+        );
+    }
+
+    #[test]
+    fn test_get_weather_series_mocked() {
+        let (today, forecast) = forecast_from_today();
+        let server = mock_server();
+        server.route("/v1/forecast", 200, &forecast);
+        let cache = temporary_cache(CacheTtl::default());
+        let provider = OpenMeteo::with_base_url(server.url()).with_cache(cache.clone());
+
+        for _ in 0..2 {
+            let series = provider
+                .get_weather_series("Mykolaiv, UA", today, today + Duration::days(2), None)
+                .unwrap();
+            assert_eq!(series.len(), 3);
+            assert_eq!(series[2].temperature, 10.2);
+        }
+
+        // The place is geocoded once, and the whole range is requested at once, both are reused from the cache.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains(&format!(
+            "start_date={}&end_date={}",
+            today,
+            today + Duration::days(2)
+        )));
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_get_weather_series_mocked_archive_and_forecast() {
+        let server = mock_server();
+        let provider = OpenMeteo::with_base_url(server.url());
+        let today = Utc::now().date_naive();
+
+        // Archive dates don't match the requested ones, so the fixture's days are missing.
+        let result =
+            provider.get_weather_series("Mykolaiv", today - Duration::days(7), today, None);
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
+
+        let requests = server.requests();
+        assert!(requests[1].starts_with("/v1/archive"));
+        assert!(requests[1].contains(&format!(
+            "end_date={}",
+            today - Duration::days(ARCHIVE_DELAY_DAYS)
+        )));
+        assert!(requests[2].starts_with("/v1/forecast"));
+        assert!(requests[2].contains(&format!(
+            "start_date={}",
+            today - Duration::days(ARCHIVE_DELAY_DAYS - 1)
+        )));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_get_open_meteo_current() {
+        let weather = OpenMeteo::new().get_current_weather("Mykolaiv, Lviv oblast, Ukraine");
+        assert!(weather.is_ok());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_get_open_meteo_timed_history() {
+        let weather = OpenMeteo::new().get_timed_weather("L'aquila, Italy", "2009-04-06", None);
+        assert!(weather.is_ok());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_get_open_meteo_timed_tommorow_weather() {
+        let tommorow = Utc::now() + Duration::days(1);
+        let weather = OpenMeteo::new().get_timed_weather(
+            "Odesa, Ukraine",
+            &tommorow.format("%Y-%m-%d").to_string(),
+            None,
+        );
+        assert!(weather.is_ok());
+    }
+}
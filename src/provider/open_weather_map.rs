@@ -1,98 +1,412 @@
 //! Provider implementation, powered by <https://openweathermap.org>.
 
-use anyhow::Context;
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize};
 use std::time::Duration;
 use url::Url;
 
-use super::{Provider, Weather};
+use super::{
+    days_between, Condition, ConditionKind, Coordinates, Geocoder, Place, Provider, ProviderName,
+    Weather, Wind,
+};
+use crate::cache::{Cache, CacheKey, Endpoint};
+use crate::error::{Error, Result};
 
 static TIMEOUT_SECONDS: u64 = 5;
+static BASE_URL: &str = "https://api.openweathermap.org/";
+static PROVIDER_NAME: ProviderName = ProviderName::OpenWeatherMap;
+/// Age, after which weather data isn't updated anymore and might be cached forever.
+static HISTORY_AGE_SECONDS: i64 = 24 * 60 * 60;
+/// Maximum number of geocoding candidates.
+static GEOCODING_LIMIT: u8 = 5;
 
 /// Concrete structure, which implements 'Provider' trait for open-weather-map API requests.
 pub struct OpenWeatherMap {
     https_client: Client,
     api_key: String,
+    base_url: Url,
+    cache: Option<Cache>,
 }
-#[derive(Serialize, Debug, Deserialize, Clone)]
-struct Coordinates {
+#[derive(Debug, Deserialize, Clone)]
+struct GeocodedPlace {
+    name: String,
+    state: Option<String>,
+    country: Option<String>,
     lon: f64,
     lat: f64,
 }
-#[derive(Debug, Deserialize, Serialize)]
-pub struct CurrentWeatherData {
+
+#[derive(Debug, Deserialize)]
+struct CurrentWeatherData {
     current: WeatherInfo,
     timezone: String,
-    lat: f64,
-    lon: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TimedWeatherData {
+#[derive(Debug, Deserialize)]
+struct TimedWeatherData {
     data: Vec<WeatherInfo>,
     timezone: String,
-    lat: f64,
-    lon: f64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize)]
+struct HourlyWeatherData {
+    hourly: Vec<WeatherInfo>,
+    timezone: String,
+}
+
+#[derive(Debug, Deserialize)]
 struct WeatherInfo {
+    dt: i64,
     temp: f64,
     feels_like: f64,
-    pressure: i64,
-    humidity: i64,
+    pressure: f64,
+    humidity: u8,
+    /// Visibility, m.
+    visibility: Option<f64>,
     wind_speed: f64,
-    wind_deg: i64,
+    wind_deg: u16,
+    rain: Option<Volume>,
+    snow: Option<Volume>,
     weather: Vec<ConditionInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Precipitation volume for the last hour, mm.
+#[derive(Debug, Deserialize)]
+struct Volume {
+    #[serde(rename = "1h")]
+    one_hour: f64,
+}
+
+#[derive(Debug, Deserialize)]
 struct ConditionInfo {
-    main: String,
+    id: u16,
     description: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+impl From<GeocodedPlace> for Place {
+    fn from(place: GeocodedPlace) -> Self {
+        Place {
+            name: place.name,
+            region: place.state,
+            country: place.country,
+            latitude: place.lat,
+            longitude: place.lon,
+            timezone: None,
+        }
+    }
+}
+
+impl WeatherInfo {
+    fn into_weather(self, place: &Place, timezone: String) -> Weather {
+        let condition = match self.weather.into_iter().next() {
+            Some(condition) => Condition {
+                kind: condition_kind(condition.id),
+                description: condition.description,
+            },
+            None => Condition {
+                kind: ConditionKind::Unknown,
+                description: String::new(),
+            },
+        };
+
+        Weather {
+            location: Place {
+                timezone: Some(timezone),
+                ..place.clone()
+            },
+            observed_at: Utc.timestamp_opt(self.dt, 0).single().unwrap_or_default(),
+            temperature: self.temp,
+            feels_like: Some(self.feels_like),
+            humidity: Some(self.humidity),
+            pressure: Some(self.pressure),
+            // Provider omits rain and snow volumes, when there is no precipitation.
+            precipitation: Some(
+                [self.rain, self.snow]
+                    .iter()
+                    .flatten()
+                    .map(|volume| volume.one_hour)
+                    .sum(),
+            ),
+            visibility: self.visibility.map(|visibility| visibility / 1000.0),
+            wind: Wind {
+                speed: Some(self.wind_speed),
+                direction: Some(self.wind_deg),
+            },
+            condition,
+        }
+    }
+}
+
+/// Maps open-weather-map condition code to the normalized condition kind.
+/// See <https://openweathermap.org/weather-conditions>.
+fn condition_kind(id: u16) -> ConditionKind {
+    match id {
+        200..=299 => ConditionKind::Thunderstorm,
+        300..=399 => ConditionKind::Drizzle,
+        511 => ConditionKind::Sleet,
+        500..=599 => ConditionKind::Rain,
+        611..=616 => ConditionKind::Sleet,
+        600..=699 => ConditionKind::Snow,
+        700..=799 => ConditionKind::Fog,
+        800 => ConditionKind::Clear,
+        801 | 802 => ConditionKind::PartlyCloudy,
+        803 | 804 => ConditionKind::Cloudy,
+        _ => ConditionKind::Unknown,
+    }
+}
+
+impl Geocoder for OpenWeatherMap {
+    /// Implementation of 'Geocoder' trait method. See <https://openweathermap.org/api/geocoding-api>.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of unknown 'query' or API limitations.
+    fn geocode(&self, query: &str) -> Result<Vec<Place>> {
+        if let Ok(coordinates) = query.parse::<Coordinates>() {
+            return Ok(vec![Place::at(coordinates)]);
+        }
+
+        let mut url = self.base_url.join("geo/1.0/direct")?;
+        url.query_pairs_mut()
+            .append_pair("q", query)
+            .append_pair("limit", &GEOCODING_LIMIT.to_string())
+            .append_pair("appid", &self.api_key);
+
+        let cache_key = CacheKey::new(PROVIDER_NAME, Endpoint::Geocoding, query, None);
+        let response = self.get_response::<Vec<GeocodedPlace>>(&url, &cache_key)?;
+
+        if response.is_empty() {
+            return Err(Error::LocationNotFound(format!(
+                "No coordinates found for {}",
+                query
+            )));
+        }
+
+        Ok(response.into_iter().map(Place::from).collect())
+    }
+
+    /// Implementation of 'Geocoder' trait method. See <https://openweathermap.org/api/geocoding-api#reverse>.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of no places nearby or API limitations.
+    fn reverse_geocode(&self, coordinates: Coordinates) -> Result<Vec<Place>> {
+        let mut url = self.base_url.join("geo/1.0/reverse")?;
+        url.query_pairs_mut()
+            .append_pair("lat", &coordinates.latitude.to_string())
+            .append_pair("lon", &coordinates.longitude.to_string())
+            .append_pair("limit", &GEOCODING_LIMIT.to_string())
+            .append_pair("appid", &self.api_key);
+
+        let query = format!("reverse {}", coordinates);
+        let cache_key = CacheKey::new(PROVIDER_NAME, Endpoint::Geocoding, &query, None);
+        let response = self.get_response::<Vec<GeocodedPlace>>(&url, &cache_key)?;
+
+        if response.is_empty() {
+            return Err(Error::LocationNotFound(format!(
+                "No places found nearby {}",
+                coordinates
+            )));
+        }
+
+        Ok(response.into_iter().map(Place::from).collect())
+    }
+}
+
 impl Provider for OpenWeatherMap {
-    /// Implementation of 'Provider' trait method. Returns the required JSON object in a readable format.
+    /// Implementation of 'Provider' trait method. Returns weather, mapped into the normalized structure.
     ///
     /// # Errors:
     ///
     /// Backpropagates in case of invalid 'address', or API limitations.
-    fn get_current_weather(&self, address: &str) -> anyhow::Result<Weather> {
-        let place_coords = self.get_coordinates_per_place(address)?;
-        let response = self.get_current_weather_parsed_data(&place_coords)?;
+    fn get_current_weather(&self, address: &str) -> Result<Weather> {
+        let place = self.get_coordinates_per_place(address)?;
+        let response = self.get_current_weather_parsed_data(&place)?;
 
         Ok(response)
     }
 
-    /// Implementation of 'Provider' trait method. Returns the required JSON object in a readable format.
+    /// Implementation of 'Provider' trait method. Returns weather at the local noon of the 'date'.
     ///
     /// # Errors:
     ///
     /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
-    fn get_timed_weather(&self, address: &str, date: &str) -> anyhow::Result<Weather> {
-        let datetime = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
-
-        let midday_datetime = NaiveDateTime::new(
-            datetime,
-            NaiveTime::from_hms_opt(12, 0, 0).expect(
-                "Failed during time parameter initialization. Contact developers for proceeding.",
-            ),
-        );
+    fn get_timed_weather(
+        &self,
+        address: &str,
+        date: &str,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let datetime = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| Error::InvalidInput(format!("invalid date '{}': {}", date, err)))?;
 
-        let place_coords = self.get_coordinates_per_place(address)?;
+        let place = self.get_coordinates_per_place(address)?;
+        let timezone = self.get_place_timezone(&place, timezone)?;
         let response =
-            self.get_timed_weather_parsed_data(&place_coords, midday_datetime.timestamp())?;
+            self.get_timed_weather_parsed_data(&place, midday_timestamp(datetime, timezone))?;
 
         Ok(response)
     }
+
+    /// Implementation of 'Provider' trait method. Hours, which are covered by the 48 hours forecast, are taken from it,
+    /// while the rest of them are requested one by one.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
+    fn get_hourly_weather(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let place = self.get_coordinates_per_place(address)?;
+        let forecast = self.get_hourly_weather_parsed_data(&place)?;
+        let tz = match timezone {
+            Some(timezone) => timezone,
+            None => parse_timezone(&forecast.timezone)?,
+        };
+
+        // Days of DST transitions are 23 or 25 hours long.
+        let day_start = local_midnight_timestamp(date, tz);
+        let day_end = local_midnight_timestamp(date + chrono::Duration::days(1), tz);
+        let hours: Vec<i64> = (day_start..day_end).step_by(3600).collect();
+
+        let timezone = forecast.timezone;
+        let mut forecast: HashMap<i64, WeatherInfo> = forecast
+            .hourly
+            .into_iter()
+            .map(|weather_info| (weather_info.dt, weather_info))
+            .collect();
+
+        hours
+            .into_iter()
+            .map(|timestamp| match forecast.remove(&timestamp) {
+                Some(weather_info) => Ok(weather_info.into_weather(&place, timezone.clone())),
+                None => self.get_timed_weather_parsed_data(&place, timestamp),
+            })
+            .collect()
+    }
+
+    /// Implementation of 'Provider' trait method. The hour is taken from the 48 hours forecast, if it covers the 'instant',
+    /// otherwise it's requested separately. The hour doesn't depend on the timezone.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'instant' or API limitations.
+    fn get_weather_at(
+        &self,
+        address: &str,
+        instant: DateTime<Utc>,
+        _timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let place = self.get_coordinates_per_place(address)?;
+        let forecast = self.get_hourly_weather_parsed_data(&place)?;
+
+        let hour_start = instant.timestamp() - instant.timestamp().rem_euclid(3600);
+        match forecast
+            .hourly
+            .into_iter()
+            .find(|weather_info| weather_info.dt == hour_start)
+        {
+            Some(weather_info) => Ok(weather_info.into_weather(&place, forecast.timezone)),
+            None => self.get_timed_weather_parsed_data(&place, instant.timestamp()),
+        }
+    }
+
+    /// Implementation of 'Provider' trait method. Timezone is taken from the hourly forecast, which is likely cached.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or API limitations.
+    fn get_timezone(&self, address: &str) -> Result<Tz> {
+        let place = self.get_coordinates_per_place(address)?;
+        self.get_place_timezone(&place, None)
+    }
+
+    /// Implementation of 'Provider' trait method. Resolves the 'address' and its timezone once and requests weather
+    /// at the local noon of every date.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or dates or API limitations.
+    fn get_weather_series(
+        &self,
+        address: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let place = self.get_coordinates_per_place(address)?;
+        let timezone = self.get_place_timezone(&place, timezone)?;
+
+        days_between(from, to)
+            .map(|date| {
+                self.get_timed_weather_parsed_data(&place, midday_timestamp(date, timezone))
+            })
+            .collect()
+    }
+}
+
+/// Timestamp of the local noon of the 'date' in the location's 'timezone'.
+fn midday_timestamp(date: NaiveDate, timezone: Tz) -> i64 {
+    local_timestamp(
+        date,
+        NaiveTime::from_hms_opt(12, 0, 0).expect(
+            "Failed during time parameter initialization. Contact developers for proceeding.",
+        ),
+        timezone,
+    )
+}
+
+/// Timestamp of the local start of the 'date' in the location's 'timezone'.
+fn local_midnight_timestamp(date: NaiveDate, timezone: Tz) -> i64 {
+    local_timestamp(date, NaiveTime::default(), timezone)
+}
+
+fn local_timestamp(date: NaiveDate, time: NaiveTime, timezone: Tz) -> i64 {
+    let local = NaiveDateTime::new(date, time);
+    // Time, skipped by the DST transition, is taken as if the clock hadn't moved yet.
+    match timezone.from_local_datetime(&local).earliest() {
+        Some(date_time) => date_time.timestamp(),
+        None => timezone
+            .from_local_datetime(&(local + chrono::Duration::hours(1)))
+            .earliest()
+            .map(|date_time| date_time.timestamp() - 3600)
+            .unwrap_or_else(|| local.timestamp()),
+    }
+}
+
+fn parse_timezone(timezone: &str) -> Result<Tz> {
+    timezone.parse().map_err(|_| {
+        Error::Upstream(format!(
+            "open-weather-map returned unknown timezone '{}'",
+            timezone
+        ))
+    })
 }
 
 impl OpenWeatherMap {
     /// Creates new entity of open-weather-map provider with set api_key.
     pub fn new(api_key: String) -> OpenWeatherMap {
+        let base_url = Url::parse(BASE_URL).expect(
+            "Invalid base URL of open-weather-map provider. Contact developers for proceeding.",
+        );
+        Self::with_base_url(api_key, base_url)
+    }
+
+    /// Creates new entity of open-weather-map provider with set api_key, which sends requests to the 'base_url'
+    /// instead of <https://api.openweathermap.org>, e.g. to a proxy or a local stand-in.
+    pub fn with_base_url(api_key: String, base_url: Url) -> OpenWeatherMap {
         let https_client = reqwest::blocking::Client::builder()
             .timeout(Duration::from_secs(TIMEOUT_SECONDS))
             .build()
@@ -101,70 +415,153 @@ impl OpenWeatherMap {
         OpenWeatherMap {
             https_client,
             api_key,
+            base_url,
+            cache: None,
         }
     }
 
-    fn get_response(&self, uri: &str) -> reqwest::Result<reqwest::blocking::Response> {
-        self.https_client.get(uri).send()
+    /// Makes provider reuse responses from the 'cache' and store the new ones there.
+    pub fn with_cache(mut self, cache: Cache) -> OpenWeatherMap {
+        self.cache = Some(cache);
+        self
     }
 
-    fn get_coordinates_per_place(&self, address: &str) -> anyhow::Result<Coordinates> {
-        let mut url = Url::parse("http://api.openweathermap.org/geo/1.0/direct")?;
-        url.query_pairs_mut()
-            .append_pair("q", address)
-            .append_pair("limit", "1")
-            .append_pair("appid", &self.api_key);
+    /// Performs request and parses successful response, or maps the failed one into the respective error.
+    /// Fresh cached response under 'cache_key' is used instead of the request, if there is one.
+    fn get_response<T: DeserializeOwned>(&self, url: &Url, cache_key: &CacheKey) -> Result<T> {
+        if let Some(body) = self.cache.as_ref().and_then(|cache| cache.get(cache_key)) {
+            if let Ok(response) = serde_json::from_str(&body) {
+                return Ok(response);
+            }
+        }
 
-        let response = self
-            .get_response(url.as_str())?
-            .json::<Vec<Coordinates>>()
-            .with_context(|| anyhow::anyhow!("Failed to parse response from openweathermap"))?;
+        let response = self.https_client.get(url.as_str()).send()?;
+        let status = response.status();
+        let body = response.text()?;
 
-        if let Some(coordinates) = response.get(0) {
-            Ok(coordinates.clone())
-        } else {
-            Err(anyhow::anyhow!("No coordinates found for {}", address))
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), &body));
+        }
+
+        let response = serde_json::from_str(&body).map_err(|err| {
+            Error::Upstream(format!("open-weather-map returned invalid data: {}", err))
+        })?;
+
+        if let Some(cache) = &self.cache {
+            // Failing to cache the response shouldn't fail the request.
+            cache.put(cache_key, &body).ok();
+        }
+
+        Ok(response)
+    }
+
+    /// Geocodes the 'address' into the best matching place. Coordinates are passed through without requests,
+    /// so the place is named after the coordinates themselves.
+    fn get_coordinates_per_place(&self, address: &str) -> Result<Place> {
+        if let Ok(coordinates) = address.parse::<Coordinates>() {
+            return Ok(Place::at(coordinates));
+        }
+
+        let place = self.geocode(address)?.into_iter().next().ok_or_else(|| {
+            Error::LocationNotFound(format!("No coordinates found for {}", address))
+        })?;
+
+        Ok(place)
+    }
+
+    /// Returns the known 'timezone', or the one of the hourly forecast of the 'place', which is likely cached.
+    fn get_place_timezone(&self, place: &Place, timezone: Option<Tz>) -> Result<Tz> {
+        match timezone {
+            Some(timezone) => Ok(timezone),
+            None => parse_timezone(&self.get_hourly_weather_parsed_data(place)?.timezone),
         }
     }
 
-    fn get_current_weather_parsed_data(&self, coords: &Coordinates) -> anyhow::Result<Weather> {
-        let mut url = Url::parse("https://api.openweathermap.org/data/3.0/onecall")?;
+    fn get_current_weather_parsed_data(&self, place: &Place) -> Result<Weather> {
+        let mut url = self.base_url.join("data/3.0/onecall")?;
         url.query_pairs_mut()
-            .append_pair("lat", &coords.lat.to_string())
-            .append_pair("lon", &coords.lon.to_string())
+            .append_pair("lat", &place.latitude.to_string())
+            .append_pair("lon", &place.longitude.to_string())
             .append_pair("exclude", "daily")
             .append_pair("exclude", "minutely")
             .append_pair("exclude", "hourly")
             .append_pair("appid", &self.api_key)
+            // Weather is normalized in metric units, and converted into the selected ones only for the output.
             .append_pair("units", "metric");
 
-        let response = self
-            .get_response(url.as_str())?
-            .json::<CurrentWeatherData>()
-            .with_context(|| anyhow::anyhow!("open-weather-map returned invalid data"))?;
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            Endpoint::Current,
+            &place.coordinates().to_string(),
+            None,
+        );
+        let response = self.get_response::<CurrentWeatherData>(&url, &cache_key)?;
 
-        Ok(Weather::FromOpenWeatherMapCurrent(response))
+        Ok(response.current.into_weather(place, response.timezone))
     }
 
-    fn get_timed_weather_parsed_data(
-        &self,
-        coords: &Coordinates,
-        timestamp: i64,
-    ) -> anyhow::Result<Weather> {
-        let mut url = Url::parse("https://api.openweathermap.org/data/3.0/onecall/timemachine")?;
+    fn get_hourly_weather_parsed_data(&self, place: &Place) -> Result<HourlyWeatherData> {
+        let mut url = self.base_url.join("data/3.0/onecall")?;
         url.query_pairs_mut()
-            .append_pair("lat", &coords.lat.to_string())
-            .append_pair("lon", &coords.lon.to_string())
+            .append_pair("lat", &place.latitude.to_string())
+            .append_pair("lon", &place.longitude.to_string())
+            .append_pair("exclude", "current,minutely,daily,alerts")
+            .append_pair("appid", &self.api_key)
+            .append_pair("units", "metric");
+
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            Endpoint::Forecast,
+            &place.coordinates().to_string(),
+            Some("hourly"),
+        );
+        self.get_response::<HourlyWeatherData>(&url, &cache_key)
+    }
+
+    fn get_timed_weather_parsed_data(&self, place: &Place, timestamp: i64) -> Result<Weather> {
+        let mut url = self.base_url.join("data/3.0/onecall/timemachine")?;
+        url.query_pairs_mut()
+            .append_pair("lat", &place.latitude.to_string())
+            .append_pair("lon", &place.longitude.to_string())
             .append_pair("dt", &timestamp.to_string())
             .append_pair("appid", &self.api_key)
             .append_pair("units", "metric");
 
-        let response = self
-            .get_response(url.as_str())?
-            .json::<TimedWeatherData>()
-            .with_context(|| anyhow::anyhow!("open-weather-map returned invalid data. Make sure your request has a reasonable date(not more, than 3 days in the future)"))?;
+        let endpoint = if timestamp < Utc::now().timestamp() - HISTORY_AGE_SECONDS {
+            Endpoint::History
+        } else {
+            Endpoint::Forecast
+        };
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            endpoint,
+            &place.coordinates().to_string(),
+            Some(&timestamp.to_string()),
+        );
+        let response = self.get_response::<TimedWeatherData>(&url, &cache_key)?;
+
+        let weather_info = response.data.into_iter().next().ok_or_else(|| {
+            Error::DateOutOfRange(String::from(
+                "open-weather-map returned no data for the requested date",
+            ))
+        })?;
 
-        Ok(Weather::FromOpenWeatherMapTimed(response))
+        Ok(weather_info.into_weather(place, response.timezone))
+    }
+}
+
+/// Maps open-weather-map error response into the respective error category.
+/// See <https://openweathermap.org/api/one-call-3#errorstructure>.
+fn api_error(status: u16, body: &str) -> Error {
+    let message = serde_json::from_str::<ErrorResponse>(body)
+        .map(|response| response.message)
+        .unwrap_or_else(|_| format!("open-weather-map responded with HTTP {}", status));
+
+    match status {
+        400 if message.contains("range") || message.contains("dt") => {
+            Error::DateOutOfRange(message)
+        }
+        _ => Error::from_status(status, message),
     }
 }
 
@@ -172,16 +569,419 @@ impl OpenWeatherMap {
 mod tests {
     use super::*;
 
-    use crate::provider::ProviderName;
-    use chrono::{Duration, Utc};
+    use crate::cache::{tests::temporary_cache, CacheTtl};
+    use crate::mock_server::{fixture, MockServer};
+    use chrono::{Duration, Timelike, Utc};
     use dotenvy::dotenv;
 
     lazy_static::lazy_static! {
         static ref API_KEY: String = {
             let provider_name = ProviderName::OpenWeatherMap;
             dotenv().ok();
-            std::env::var(provider_name.to_string()).expect(format!("{}_API_KEY not found in .env", provider_name).as_str())
+            std::env::var(provider_name.to_string()).unwrap_or_else(|_| panic!("{}_API_KEY not found in .env", provider_name))
+        };
+    }
+
+    #[test]
+    fn test_current_weather_data_into_weather() {
+        let place = Place {
+            name: String::from("Lviv"),
+            region: Some(String::from("Lviv Oblast")),
+            country: Some(String::from("UA")),
+            latitude: 49.84,
+            longitude: 24.03,
+            timezone: None,
         };
+        let data: CurrentWeatherData = serde_json::from_str(
+            r#"{
+                "lat": 49.84, "lon": 24.03, "timezone": "Europe/Kyiv", "timezone_offset": 10800,
+                "current": {
+                    "dt": 1680872400, "temp": 11.5, "feels_like": 10.2, "pressure": 1015,
+                    "humidity": 71, "wind_speed": 3.6, "wind_deg": 250,
+                    "weather": [{"id": 803, "main": "Clouds", "description": "broken clouds"}]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let weather = data.current.into_weather(&place, data.timezone);
+        assert_eq!(weather.location.name, "Lviv");
+        assert_eq!(weather.location.timezone.as_deref(), Some("Europe/Kyiv"));
+        assert_eq!(weather.observed_at.timestamp(), 1680872400);
+        assert_eq!(weather.temperature, 11.5);
+        assert_eq!(weather.humidity, Some(71));
+        assert_eq!(weather.wind.direction, Some(250));
+        assert_eq!(weather.condition.kind, ConditionKind::Cloudy);
+        assert_eq!(weather.condition.description, "broken clouds");
+    }
+
+    #[test]
+    fn test_local_timestamps() {
+        let date = NaiveDate::from_ymd_opt(2023, 4, 7).unwrap();
+        assert_eq!(midday_timestamp(date, chrono_tz::Europe::Kiev), 1680858000);
+        assert_eq!(midday_timestamp(date, chrono_tz::UTC), 1680868800);
+        assert_eq!(
+            midday_timestamp(date, chrono_tz::America::Los_Angeles),
+            1680894000
+        );
+
+        // Kiritimati is 14 hours ahead of UTC, so its noon is still the previous day in UTC.
+        let noon = midday_timestamp(date, chrono_tz::Pacific::Kiritimati);
+        assert_eq!(noon, 1680818400);
+        let local_noon = Utc
+            .timestamp_opt(noon, 0)
+            .unwrap()
+            .with_timezone(&chrono_tz::Pacific::Kiritimati);
+        assert_eq!(local_noon.date_naive(), date);
+        assert_eq!(local_noon.hour(), 12);
+
+        // Clocks were moved forward on 2023-03-26 in Kyiv.
+        let date = NaiveDate::from_ymd_opt(2023, 3, 26).unwrap();
+        let day_length =
+            local_midnight_timestamp(date + chrono::Duration::days(1), chrono_tz::Europe::Kiev)
+                - local_midnight_timestamp(date, chrono_tz::Europe::Kiev);
+        assert_eq!(day_length, 23 * 3600);
+    }
+
+    #[test]
+    fn test_api_error() {
+        let error = api_error(401, r#"{"cod": 401, "message": "Invalid API key."}"#);
+        assert!(matches!(error, Error::Credentials(message) if message == "Invalid API key."));
+
+        let error = api_error(
+            400,
+            r#"{"cod": "400", "message": "requested time is out of allowed range"}"#,
+        );
+        assert!(matches!(error, Error::DateOutOfRange(_)));
+
+        let error = api_error(
+            429,
+            r#"{"cod": 429, "message": "Your account is temporary blocked"}"#,
+        );
+        assert!(matches!(error, Error::QuotaExceeded(_)));
+
+        let error = api_error(502, "<html>Bad Gateway</html>");
+        assert!(matches!(error, Error::Upstream(_)));
+    }
+
+    fn mock_server() -> MockServer {
+        let server = MockServer::start();
+        server
+            .route(
+                "/geo/1.0/direct",
+                200,
+                &fixture("open_weather_map/geocoding.json"),
+            )
+            .route(
+                "/geo/1.0/reverse",
+                200,
+                &fixture("open_weather_map/reverse_geocoding.json"),
+            )
+            .route(
+                "/data/3.0/onecall",
+                200,
+                &fixture("open_weather_map/onecall.json"),
+            )
+            .route_with_query(
+                "/data/3.0/onecall",
+                "exclude=current",
+                200,
+                &fixture("open_weather_map/onecall_hourly.json"),
+            )
+            .route(
+                "/data/3.0/onecall/timemachine",
+                200,
+                &fixture("open_weather_map/timemachine.json"),
+            );
+        server
+    }
+
+    fn mocked_provider(server: &MockServer) -> OpenWeatherMap {
+        OpenWeatherMap::with_base_url(String::from("test_key"), server.url())
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked() {
+        let server = mock_server();
+        let weather = mocked_provider(&server)
+            .get_current_weather("Mykolaiv, Lviv oblast, Ukraine")
+            .unwrap();
+
+        assert_eq!(weather.location.name, "Mykolaiv");
+        assert_eq!(weather.location.region.as_deref(), Some("Lviv Oblast"));
+        assert_eq!(weather.temperature, 9.64);
+        assert_eq!(weather.pressure, Some(1016.0));
+        assert_eq!(weather.precipitation, Some(0.0));
+        assert_eq!(weather.visibility, Some(10.0));
+        assert_eq!(weather.condition.kind, ConditionKind::Cloudy);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("q=Mykolaiv%2C+Lviv+oblast%2C+Ukraine"));
+        assert!(requests
+            .iter()
+            .all(|request| request.contains("appid=test_key")));
+    }
+
+    #[test]
+    fn test_geocode_mocked() {
+        let server = mock_server();
+        server.route(
+            "/geo/1.0/direct",
+            200,
+            &fixture("open_weather_map/geocoding_ambiguous.json"),
+        );
+        let provider = mocked_provider(&server);
+
+        let places = provider.geocode("Mykolaiv").unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(
+            places[1].to_string(),
+            "Mykolaiv, Lviv Oblast, UA (49.52, 23.98)"
+        );
+        assert!(server.requests()[0].contains("limit=5"));
+
+        server.route("/geo/1.0/direct", 200, "[]");
+        assert!(matches!(
+            provider.geocode("SO INVALID ADDRESS"),
+            Err(Error::LocationNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_coordinates() {
+        let server = mock_server();
+        let weather = mocked_provider(&server)
+            .get_current_weather("49.84, 24.03")
+            .unwrap();
+
+        assert_eq!(weather.location.latitude, 49.84);
+        assert_eq!(weather.location.longitude, 24.03);
+        assert_eq!(weather.location.name, "49.84,24.03");
+
+        // Coordinates are neither geocoded, nor reverse geocoded.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("lat=49.84&lon=24.03"));
+    }
+
+    #[test]
+    fn test_reverse_geocode_mocked() {
+        let server = mock_server();
+        let places = mocked_provider(&server)
+            .reverse_geocode("49.84,24.03".parse().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            places[0].to_string(),
+            "Lviv, Lviv Oblast, UA (49.84, 24.03)"
+        );
+        assert!(server.requests()[0].contains("lat=49.84&lon=24.03&limit=5"));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_cached() {
+        let server = mock_server();
+        let cache = temporary_cache(CacheTtl::default());
+        let provider = mocked_provider(&server).with_cache(cache.clone());
+
+        let weather = provider.get_current_weather("Mykolaiv").unwrap();
+        let cached_weather = provider.get_current_weather(" mykolaiv ").unwrap();
+        assert_eq!(weather, cached_weather);
+        assert_eq!(server.requests().len(), 2);
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries, 2);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked() {
+        let server = mock_server();
+        server
+            .route(
+                "/data/3.0/onecall/timemachine",
+                400,
+                &fixture("open_weather_map/error_400_dt.json"),
+            )
+            // Local noon in Mykolaiv.
+            .route_with_query(
+                "/data/3.0/onecall/timemachine",
+                "dt=1680858000",
+                200,
+                &fixture("open_weather_map/timemachine.json"),
+            );
+
+        let weather = mocked_provider(&server)
+            .get_timed_weather("Mykolaiv, Lviv oblast, Ukraine", "2023-04-07", None)
+            .unwrap();
+
+        assert_eq!(weather.temperature, 8.23);
+        assert_eq!(weather.condition.kind, ConditionKind::Rain);
+        // Timezone is taken from the hourly forecast.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].contains("exclude=current"));
+        assert!(requests[2].starts_with("/data/3.0/onecall/timemachine"));
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_date_line() {
+        let server = mock_server();
+        server
+            .route(
+                "/data/3.0/onecall/timemachine",
+                400,
+                &fixture("open_weather_map/error_400_dt.json"),
+            )
+            // Local noon in Kiritimati, which is on the previous day in UTC.
+            .route_with_query(
+                "/data/3.0/onecall/timemachine",
+                "dt=1680818400",
+                200,
+                &fixture("open_weather_map/timemachine.json"),
+            );
+
+        mocked_provider(&server)
+            .get_timed_weather(
+                "1.87,-157.4",
+                "2023-04-07",
+                Some(chrono_tz::Pacific::Kiritimati),
+            )
+            .unwrap();
+        // Known timezone isn't requested.
+        assert!(!server
+            .requests()
+            .iter()
+            .any(|request| request.contains("exclude=current")));
+    }
+
+    #[test]
+    fn test_get_hourly_weather_mocked() {
+        let server = mock_server();
+        let provider = mocked_provider(&server);
+        let date = NaiveDate::from_ymd_opt(2023, 4, 7).unwrap();
+
+        let hourly = provider
+            .get_hourly_weather("Mykolaiv, Lviv oblast, Ukraine", date, None)
+            .unwrap();
+        assert_eq!(hourly.len(), 24);
+        // Hours before 16:00 local time aren't covered by the forecast and are requested one by one.
+        assert_eq!(
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.starts_with("/data/3.0/onecall/timemachine"))
+                .count(),
+            16
+        );
+        assert_eq!(hourly[16].observed_at.timestamp(), 1680872400);
+        assert_eq!(hourly[16].temperature, 8.73);
+        assert_eq!(hourly[23].location.timezone.as_deref(), Some("Europe/Kiev"));
+    }
+
+    #[test]
+    fn test_get_weather_at_mocked() {
+        let server = mock_server();
+        let provider = mocked_provider(&server);
+
+        // 20:30 in Kyiv, which is covered by the forecast.
+        let instant = Utc.timestamp_opt(1680872400 + 4 * 3600 + 1800, 0).unwrap();
+        let weather = provider.get_weather_at("Mykolaiv", instant, None).unwrap();
+        assert_eq!(weather.observed_at.timestamp(), 1680872400 + 4 * 3600);
+        assert!(!server
+            .requests()
+            .iter()
+            .any(|request| request.starts_with("/data/3.0/onecall/timemachine")));
+
+        let instant = Utc.timestamp_opt(1680728400, 0).unwrap();
+        provider.get_weather_at("Mykolaiv", instant, None).unwrap();
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.contains("timemachine") && request.contains("dt=1680728400")));
+
+        assert_eq!(
+            provider.get_timezone("Mykolaiv").unwrap(),
+            chrono_tz::Europe::Kiev
+        );
+    }
+
+    #[test]
+    fn test_get_weather_series_mocked() {
+        let server = mock_server();
+        let from = NaiveDate::from_ymd_opt(2023, 4, 5).unwrap();
+        let to = NaiveDate::from_ymd_opt(2023, 4, 7).unwrap();
+
+        let series = mocked_provider(&server)
+            .get_weather_series("Mykolaiv, Lviv oblast, Ukraine", from, to, None)
+            .unwrap();
+        assert_eq!(series.len(), 3);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| request.starts_with("/geo/1.0/direct"))
+                .count(),
+            1
+        );
+        assert_eq!(requests.len(), 5);
+        assert!(requests[1].contains("exclude=current"));
+        assert!(requests[2].contains("dt=1680685200"));
+        assert!(requests[4].contains("dt=1680858000"));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_unknown_address() {
+        let server = mock_server();
+        server.route(
+            "/geo/1.0/direct",
+            200,
+            &fixture("open_weather_map/not_found.json"),
+        );
+
+        let result = mocked_provider(&server).get_current_weather("SO INVALID ADDRESS");
+        assert!(matches!(result, Err(Error::LocationNotFound(_))));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_error_responses() {
+        let cases = [
+            (401, fixture("open_weather_map/error_401.json")),
+            (
+                404,
+                String::from(r#"{"cod": "404", "message": "Not found"}"#),
+            ),
+            (429, fixture("open_weather_map/error_429.json")),
+            (200, String::from("{\"lat\": 49.5237, \"current\": ")),
+        ];
+
+        for (status, body) in cases {
+            let server = mock_server();
+            server.route("/data/3.0/onecall", status, &body);
+
+            let result = mocked_provider(&server).get_current_weather("Mykolaiv");
+            match status {
+                401 => assert!(matches!(result, Err(Error::Credentials(_)))),
+                404 => assert!(matches!(result, Err(Error::LocationNotFound(_)))),
+                429 => assert!(matches!(result, Err(Error::QuotaExceeded(_)))),
+                _ => assert!(matches!(result, Err(Error::Upstream(_)))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_date_out_of_range() {
+        let server = mock_server();
+        server.route(
+            "/data/3.0/onecall/timemachine",
+            400,
+            &fixture("open_weather_map/error_400_dt.json"),
+        );
+
+        let result = mocked_provider(&server).get_timed_weather("Mykolaiv", "1800-04-01", None);
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
     }
 
     #[test]
@@ -212,6 +1012,7 @@ mod tests {
         let weather = provider.get_timed_weather(
             "Mykolaiv, Lviv oblast, Ukraine",
             &formatted_yesterday.to_string(),
+            None,
         );
         assert!(weather.is_ok());
     }
@@ -228,6 +1029,7 @@ mod tests {
         let weather = provider.get_timed_weather(
             "Mykolaiv, Lviv oblast, Ukraine",
             &formatted_tommorow.to_string(),
+            None,
         );
         assert!(weather.is_ok());
     }
@@ -237,7 +1039,7 @@ mod tests {
     fn test_get_open_weather_map_timed_invalid_timestamp() {
         let provider = OpenWeatherMap::new(API_KEY.to_string());
         let date = "988-04-01";
-        let result = provider.get_timed_weather("Mykolaiv, Lviv oblast, Ukraine", date);
+        let result = provider.get_timed_weather("Mykolaiv, Lviv oblast, Ukraine", date, None);
         assert!(result.is_err());
     }
 }
@@ -1,97 +1,482 @@
 //! Provider implementation, powered by <https://www.weatherapi.com>.
 use std::time::Duration;
 
-use anyhow::Context;
-use chrono::{Local, NaiveDate};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize};
 use url::Url;
 
-use super::{Provider, Weather};
+use super::{
+    days_between, Condition, ConditionKind, Coordinates, Geocoder, Place, Provider, ProviderName,
+    Weather, Wind,
+};
+use crate::cache::{Cache, CacheKey, Endpoint};
+use crate::error::{Error, Result};
 
 static TIMEOUT_SECONDS: u64 = 5;
-static WEATHER_API_ERROR: &str = "weather-api returned invalid data. \
-        If your input is correct, this might be caused by limitations of current provider";
+static BASE_URL: &str = "https://api.weatherapi.com/v1/";
+static PROVIDER_NAME: ProviderName = ProviderName::WeatherApi;
+static WEATHER_API_ERROR: &str = "weather-api returned invalid data";
+static LIMITATIONS_HINT: &str =
+    "If your input is correct, this might be caused by limitations of current provider";
 
 /// Concrete structure, which implements 'Provider' trait for weather-api API requests.
 pub struct WeatherApi {
     api_key: String,
     https_client: Client,
+    base_url: Url,
+    cache: Option<Cache>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct CurrentWeatherData {
+#[derive(Debug, Deserialize)]
+struct CurrentWeatherData {
     current: WeatherInfo,
     location: Location,
 }
-#[derive(Debug, Deserialize, Serialize)]
-pub struct TimedWeatherData {
+#[derive(Debug, Deserialize)]
+struct TimedWeatherData {
     forecast: Forecast,
     location: Location,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Clone)]
 struct Location {
     name: String,
     region: String,
     country: String,
+    lat: f64,
+    lon: f64,
+    tz_id: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Place, found by the search, which unlike the weather's location has no timezone.
+#[derive(Debug, Deserialize)]
+struct SearchResult {
+    name: String,
+    region: String,
+    country: String,
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Debug, Deserialize)]
 struct WeatherInfo {
+    last_updated_epoch: i64,
     temp_c: f64,
-    temp_f: f64,
+    feelslike_c: f64,
+    humidity: u8,
+    pressure_mb: f64,
+    precip_mm: Option<f64>,
+    vis_km: Option<f64>,
+    wind_kph: f64,
+    wind_degree: u16,
     condition: ConditionInfo,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize)]
 struct Forecast {
     forecastday: Vec<ForecastDay>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize)]
 struct ForecastDay {
+    date: NaiveDate,
+    date_epoch: i64,
     day: Day,
+    #[serde(default)]
+    hour: Vec<HourInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourInfo {
+    time_epoch: i64,
+    temp_c: f64,
+    feelslike_c: f64,
+    humidity: u8,
+    pressure_mb: f64,
+    precip_mm: Option<f64>,
+    vis_km: Option<f64>,
+    wind_kph: f64,
+    wind_degree: u16,
+    condition: ConditionInfo,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize)]
 struct Day {
     avgtemp_c: f64,
-    avgtemp_f: f64,
-    maxwind_mph: f64,
     maxwind_kph: f64,
+    avghumidity: f64,
+    totalprecip_mm: Option<f64>,
+    avgvis_km: Option<f64>,
     condition: ConditionInfo,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize)]
 struct ConditionInfo {
     text: String,
+    code: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ErrorInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorInfo {
+    code: u16,
+    message: String,
+}
+
+impl From<SearchResult> for Place {
+    fn from(result: SearchResult) -> Self {
+        Place {
+            name: result.name,
+            region: Some(result.region).filter(|region| !region.is_empty()),
+            country: Some(result.country).filter(|country| !country.is_empty()),
+            latitude: result.lat,
+            longitude: result.lon,
+            timezone: None,
+        }
+    }
+}
+
+impl From<Location> for Place {
+    fn from(location: Location) -> Self {
+        Place {
+            name: location.name,
+            region: Some(location.region).filter(|region| !region.is_empty()),
+            country: Some(location.country).filter(|country| !country.is_empty()),
+            latitude: location.lat,
+            longitude: location.lon,
+            timezone: Some(location.tz_id),
+        }
+    }
+}
+
+impl From<ConditionInfo> for Condition {
+    fn from(condition: ConditionInfo) -> Self {
+        Condition {
+            kind: condition_kind(condition.code),
+            description: condition.text,
+        }
+    }
+}
+
+impl From<CurrentWeatherData> for Weather {
+    fn from(data: CurrentWeatherData) -> Self {
+        let current = data.current;
+
+        Weather {
+            location: data.location.into(),
+            observed_at: from_epoch(current.last_updated_epoch),
+            temperature: current.temp_c,
+            feels_like: Some(current.feelslike_c),
+            humidity: Some(current.humidity),
+            pressure: Some(current.pressure_mb),
+            precipitation: current.precip_mm,
+            visibility: current.vis_km,
+            wind: Wind {
+                speed: Some(kph_to_meters_per_second(current.wind_kph)),
+                direction: Some(current.wind_degree),
+            },
+            condition: current.condition.into(),
+        }
+    }
+}
+
+impl ForecastDay {
+    fn into_weather(self, location: Location) -> Weather {
+        Weather {
+            location: location.into(),
+            observed_at: from_epoch(self.date_epoch),
+            temperature: self.day.avgtemp_c,
+            feels_like: None,
+            humidity: Some(self.day.avghumidity.round() as u8),
+            pressure: None,
+            precipitation: self.day.totalprecip_mm,
+            visibility: self.day.avgvis_km,
+            wind: Wind {
+                speed: Some(kph_to_meters_per_second(self.day.maxwind_kph)),
+                direction: None,
+            },
+            condition: self.day.condition.into(),
+        }
+    }
+}
+
+impl HourInfo {
+    fn into_weather(self, location: Location) -> Weather {
+        Weather {
+            location: location.into(),
+            observed_at: from_epoch(self.time_epoch),
+            temperature: self.temp_c,
+            feels_like: Some(self.feelslike_c),
+            humidity: Some(self.humidity),
+            pressure: Some(self.pressure_mb),
+            precipitation: self.precip_mm,
+            visibility: self.vis_km,
+            wind: Wind {
+                speed: Some(kph_to_meters_per_second(self.wind_kph)),
+                direction: Some(self.wind_degree),
+            },
+            condition: self.condition.into(),
+        }
+    }
+}
+
+fn from_epoch(epoch: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(epoch, 0).single().unwrap_or_default()
+}
+
+fn kph_to_meters_per_second(kph: f64) -> f64 {
+    kph / 3.6
+}
+
+/// Maps weather-api condition code to the normalized condition kind.
+/// See <https://www.weatherapi.com/docs/weather_conditions.json>.
+fn condition_kind(code: u16) -> ConditionKind {
+    match code {
+        1000 => ConditionKind::Clear,
+        1003 => ConditionKind::PartlyCloudy,
+        1006 | 1009 => ConditionKind::Cloudy,
+        1030 | 1135 | 1147 => ConditionKind::Fog,
+        1072 | 1150 | 1153 | 1168 | 1171 => ConditionKind::Drizzle,
+        1063 | 1180..=1201 | 1240..=1246 => ConditionKind::Rain,
+        1069 | 1204..=1207 | 1237 | 1249 | 1252 | 1261 | 1264 => ConditionKind::Sleet,
+        1066 | 1114 | 1117 | 1210..=1225 | 1255 | 1258 => ConditionKind::Snow,
+        1087 | 1273..=1282 => ConditionKind::Thunderstorm,
+        _ => ConditionKind::Unknown,
+    }
+}
+
+impl Geocoder for WeatherApi {
+    /// Implementation of 'Geocoder' trait method. See <https://www.weatherapi.com/docs/#apis-search>.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of unknown 'query' or API limitations.
+    fn geocode(&self, query: &str) -> Result<Vec<Place>> {
+        if let Ok(coordinates) = query.parse::<Coordinates>() {
+            return Ok(vec![Place::at(coordinates)]);
+        }
+
+        let mut url = self.base_url.join("search.json")?;
+        url.query_pairs_mut()
+            .append_pair("key", &self.api_key)
+            .append_pair("q", query);
+
+        let cache_key = CacheKey::new(PROVIDER_NAME, Endpoint::Geocoding, query, None);
+        let response = self.get_response::<Vec<SearchResult>>(&url, &cache_key)?;
+
+        if response.is_empty() {
+            return Err(Error::LocationNotFound(format!(
+                "No matching location found for {}",
+                query
+            )));
+        }
+
+        Ok(response.into_iter().map(Place::from).collect())
+    }
+
+    /// Implementation of 'Geocoder' trait method. The search resolves coordinates into places nearby.
+    /// See <https://www.weatherapi.com/docs/#apis-search>.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of no places nearby or API limitations.
+    fn reverse_geocode(&self, coordinates: Coordinates) -> Result<Vec<Place>> {
+        let query = coordinates.to_string();
+        let mut url = self.base_url.join("search.json")?;
+        url.query_pairs_mut()
+            .append_pair("key", &self.api_key)
+            .append_pair("q", &query);
+
+        let cache_key = CacheKey::new(PROVIDER_NAME, Endpoint::Geocoding, &query, None);
+        let response = self.get_response::<Vec<SearchResult>>(&url, &cache_key)?;
+
+        if response.is_empty() {
+            return Err(Error::LocationNotFound(format!(
+                "No places found nearby {}",
+                coordinates
+            )));
+        }
+
+        Ok(response.into_iter().map(Place::from).collect())
+    }
 }
 
 impl Provider for WeatherApi {
-    /// Implementation of 'Provider' trait method. Returns the required JSON object in a readable format.
+    /// Implementation of 'Provider' trait method. Returns weather, mapped into the normalized structure.
     ///
     /// # Errors:
     ///
     /// Backpropagates in case of invalid 'address', or API limitations.
-    fn get_current_weather(&self, address: &str) -> anyhow::Result<Weather> {
+    fn get_current_weather(&self, address: &str) -> Result<Weather> {
         let response = self.get_current_weather_data(address)?;
         Ok(response)
     }
 
-    /// Implementation of 'Provider' trait method. Returns the required JSON object in a readable format.
+    /// Implementation of 'Provider' trait method. Returns weather, mapped into the normalized structure.
     ///
     /// # Errors:
     ///
     /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
-    fn get_timed_weather(&self, address: &str, date: &str) -> anyhow::Result<Weather> {
-        let response = self.get_timed_weather_data(address, date)?;
+    fn get_timed_weather(
+        &self,
+        address: &str,
+        date: &str,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let response = self.get_timed_weather_data(address, date, timezone)?;
         Ok(response)
     }
+
+    /// Implementation of 'Provider' trait method. Returns entries of the day's 'hour' array,
+    /// which is already in the location's local time.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'date' or API limitations.
+    fn get_hourly_weather(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let today = self.get_today(address, date, timezone)?;
+        let (hours, location) = self.get_hours(address, date, today)?;
+
+        Ok(hours
+            .into_iter()
+            .map(|hour| hour.into_weather(location.clone()))
+            .collect())
+    }
+
+    /// Implementation of 'Provider' trait method. Returns the entry of the 'hour' array of the local date of the 'instant',
+    /// which contains the 'instant'. Timezone is requested only if it's unknown.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or 'instant' or API limitations.
+    fn get_weather_at(
+        &self,
+        address: &str,
+        instant: DateTime<Utc>,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let timezone = match timezone {
+            Some(timezone) => timezone,
+            None => self.get_timezone(address)?,
+        };
+        let date = instant.with_timezone(&timezone).date_naive();
+        let today = Utc::now().with_timezone(&timezone).date_naive();
+        let (hours, location) = self.get_hours(address, date, today)?;
+
+        let timestamp = instant.timestamp();
+        hours
+            .into_iter()
+            .find(|hour| (hour.time_epoch..hour.time_epoch + 3600).contains(&timestamp))
+            .map(|hour| hour.into_weather(location))
+            .ok_or_else(|| {
+                Error::DateOutOfRange(format!(
+                    "weather-api returned no data for {}. {}",
+                    instant, LIMITATIONS_HINT
+                ))
+            })
+    }
+
+    /// Implementation of 'Provider' trait method. Timezone is taken from the current weather, which is likely cached.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or API limitations.
+    fn get_timezone(&self, address: &str) -> Result<Tz> {
+        let weather = self.get_current_weather_data(address)?;
+        let timezone = weather.location.timezone.unwrap_or_default();
+
+        timezone.parse().map_err(|_| {
+            Error::Upstream(format!(
+                "weather-api returned unknown timezone '{}'",
+                timezone
+            ))
+        })
+    }
+
+    /// Implementation of 'Provider' trait method. Past days are requested one by one, with the location,
+    /// resolved by the first request, while future days are requested at once with the 'days' parameter.
+    ///
+    /// # Errors:
+    ///
+    /// Backpropagates in case of invalid 'address' or dates or API limitations.
+    fn get_weather_series(
+        &self,
+        address: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: Option<Tz>,
+    ) -> Result<Vec<Weather>> {
+        let now_date = self.get_today(address, to, timezone)?;
+        let mut query = address.to_owned();
+        let mut series: Vec<Weather> = Vec::new();
+
+        for date in days_between(from, to.min(now_date)) {
+            let response = self.get_history_response(&query, date)?;
+            let weather = history_day(response, date)?;
+            query = format!(
+                "{},{}",
+                weather.location.latitude, weather.location.longitude
+            );
+            series.push(weather);
+        }
+
+        if to > now_date {
+            let first_forecast_date = from.max(now_date + chrono::Duration::days(1));
+            let days_from_now = (to - now_date).num_days() + 1;
+            let response = self.get_forecast_response(&query, to, days_from_now)?;
+            let location = response.location;
+
+            let forecast: Vec<Weather> = response
+                .forecast
+                .forecastday
+                .into_iter()
+                .filter(|forecast_day| forecast_day.date >= first_forecast_date)
+                .map(|forecast_day| forecast_day.into_weather(location.clone()))
+                .collect();
+
+            // Forecast is silently truncated to the maximum number of days, allowed by the subscription plan.
+            if forecast.len() as i64 != (to - first_forecast_date).num_days() + 1 {
+                return Err(Error::DateOutOfRange(format!(
+                    "weather-api returned no data for some days up to {}. {}",
+                    to, LIMITATIONS_HINT
+                )));
+            }
+            series.extend(forecast);
+        }
+
+        // Coordinates might resolve into a different nearby place, so the whole series is reported for the first one.
+        if let Some(place) = series.first().map(|weather| weather.location.clone()) {
+            for weather in &mut series {
+                weather.location = place.clone();
+            }
+        }
+
+        Ok(series)
+    }
 }
 
 impl WeatherApi {
+    /// Creates new entity of weather-api provider with set api_key.
     pub fn new(api_key: String) -> WeatherApi {
+        let base_url = Url::parse(BASE_URL)
+            .expect("Invalid base URL of weather-api provider. Contact developers for proceeding.");
+        Self::with_base_url(api_key, base_url)
+    }
+
+    /// Creates new entity of weather-api provider with set api_key, which sends requests to the 'base_url'
+    /// instead of <https://api.weatherapi.com/v1/>, e.g. to a proxy or a local stand-in.
+    pub fn with_base_url(api_key: String, base_url: Url) -> WeatherApi {
         let https_client = reqwest::blocking::Client::builder()
             .timeout(Duration::from_secs(TIMEOUT_SECONDS))
             .build()
@@ -100,91 +485,249 @@ impl WeatherApi {
         WeatherApi {
             api_key,
             https_client,
+            base_url,
+            cache: None,
         }
     }
 
-    fn get_response(&self, uri: &str) -> reqwest::Result<reqwest::blocking::Response> {
-        self.https_client.get(uri).send()
+    /// Makes provider reuse responses from the 'cache' and store the new ones there.
+    pub fn with_cache(mut self, cache: Cache) -> WeatherApi {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Performs request and parses successful response, or maps the failed one into the respective error.
+    /// Fresh cached response under 'cache_key' is used instead of the request, if there is one.
+    fn get_response<T: DeserializeOwned>(&self, url: &Url, cache_key: &CacheKey) -> Result<T> {
+        if let Some(body) = self.cache.as_ref().and_then(|cache| cache.get(cache_key)) {
+            if let Ok(response) = serde_json::from_str(&body) {
+                return Ok(response);
+            }
+        }
+
+        let response = self.https_client.get(url.as_str()).send()?;
+        let status = response.status();
+        let body = response.text()?;
+
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), &body));
+        }
+
+        let response = serde_json::from_str(&body)
+            .map_err(|err| Error::Upstream(format!("{}: {}", WEATHER_API_ERROR, err)))?;
+
+        if let Some(cache) = &self.cache {
+            // Failing to cache the response shouldn't fail the request.
+            cache.put(cache_key, &body).ok();
+        }
+
+        Ok(response)
     }
 
-    fn get_current_weather_data(&self, address: &str) -> anyhow::Result<Weather> {
-        let mut url = Url::parse("http://api.weatherapi.com/v1/current.json")?;
+    fn get_current_weather_data(&self, address: &str) -> Result<Weather> {
+        let mut url = self.base_url.join("current.json")?;
         url.query_pairs_mut()
             .append_pair("key", &self.api_key)
-            .append_pair("q", address)
+            .append_pair("q", &query(address))
             .append_pair("aqi", "no");
 
-        let response = self
-            .get_response(url.as_str())?
-            .json::<CurrentWeatherData>()
-            .with_context(|| anyhow::anyhow!(WEATHER_API_ERROR))?;
+        let cache_key = CacheKey::new(PROVIDER_NAME, Endpoint::Current, address, None);
+        let response = self.get_response::<CurrentWeatherData>(&url, &cache_key)?;
 
-        Ok(Weather::FromWeatherApiCurrent(response))
+        Ok(response.into())
     }
 
-    fn get_timed_weather_data(&self, address: &str, date: &str) -> anyhow::Result<Weather> {
-        let date_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
-        let now_date = Local::now().date_naive();
+    /// Returns today's date in the location's timezone, which the 'date' is compared with to choose the endpoint.
+    /// Unknown timezone is requested, unless the 'date' is before UTC yesterday, so it's in the past in any timezone.
+    fn get_today(&self, address: &str, date: NaiveDate, timezone: Option<Tz>) -> Result<NaiveDate> {
+        let utc_today = Utc::now().date_naive();
+        let timezone = match timezone {
+            Some(timezone) => timezone,
+            None if date < utc_today - chrono::Duration::days(1) => return Ok(utc_today),
+            None => self.get_timezone(address)?,
+        };
+
+        Ok(Utc::now().with_timezone(&timezone).date_naive())
+    }
+
+    fn get_timed_weather_data(
+        &self,
+        address: &str,
+        date: &str,
+        timezone: Option<Tz>,
+    ) -> Result<Weather> {
+        let date_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| Error::InvalidInput(format!("invalid date '{}': {}", date, err)))?;
+        let now_date = self.get_today(address, date_date, timezone)?;
 
         match date_date.cmp(&now_date) {
             std::cmp::Ordering::Greater => {
                 let days_from_now = (date_date - now_date).num_days() + 1;
-                self.get_forecast_weather_data(address, days_from_now)
+                self.get_forecast_weather_data(address, date_date, days_from_now)
             }
-            _ => self.get_history_weather_data(address, date),
+            _ => self.get_history_weather_data(address, date_date),
         }
     }
 
     fn get_forecast_weather_data(
         &self,
         address: &str,
+        date: NaiveDate,
         days_from_now: i64,
-    ) -> anyhow::Result<Weather> {
-        let mut url = Url::parse("http://api.weatherapi.com/v1/forecast.json")?;
-        url.query_pairs_mut()
-            .append_pair("key", &self.api_key)
-            .append_pair("q", address)
-            .append_pair("days", &days_from_now.to_string())
-            .append_pair("aqi", "no")
-            .append_pair("alerts", "no");
-
-        let response = self
-            .get_response(url.as_str())?
-            .json::<TimedWeatherData>()
-            .with_context(|| anyhow::anyhow!(WEATHER_API_ERROR))?;
+    ) -> Result<Weather> {
+        let response = self.get_forecast_response(address, date, days_from_now)?;
 
+        // Forecast is silently truncated to the maximum number of days, allowed by the subscription plan.
         let last_day = response
             .forecast
             .forecastday
+            .into_iter()
             .last()
-            .ok_or(anyhow::anyhow!("weather-api returned invalid data"))?;
+            .filter(|forecast_day| forecast_day.date == date)
+            .ok_or_else(|| {
+                Error::DateOutOfRange(format!(
+                    "weather-api returned no data for {}. {}",
+                    date, LIMITATIONS_HINT
+                ))
+            })?;
 
-        let forecast = Forecast {
-            forecastday: vec![(*last_day).clone()],
-        };
+        Ok(last_day.into_weather(response.location))
+    }
 
-        let response = TimedWeatherData {
-            forecast,
-            location: response.location,
+    /// Requests hourly data of the 'date', which is in the location's local time, either from the history or the forecast,
+    /// depending on the location's 'today'.
+    fn get_hours(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        today: NaiveDate,
+    ) -> Result<(Vec<HourInfo>, Location)> {
+        let (day, location) = match date.cmp(&today) {
+            std::cmp::Ordering::Greater => {
+                let days_from_now = (date - today).num_days() + 1;
+                let response = self.get_forecast_response(address, date, days_from_now)?;
+                let day = response
+                    .forecast
+                    .forecastday
+                    .into_iter()
+                    .find(|forecast_day| forecast_day.date == date);
+                (day, response.location)
+            }
+            _ => {
+                let response = self.get_history_response(address, date)?;
+                (
+                    response.forecast.forecastday.into_iter().next(),
+                    response.location,
+                )
+            }
         };
 
-        Ok(Weather::FromWeatherApiTimed(response))
+        let hours = day.map(|day| day.hour).unwrap_or_default();
+        if hours.is_empty() {
+            return Err(Error::DateOutOfRange(format!(
+                "weather-api returned no hourly data for {}. {}",
+                date, LIMITATIONS_HINT
+            )));
+        }
+
+        Ok((hours, location))
+    }
+
+    /// Requests forecast for 'days_from_now' days, starting today and ending on the 'date'.
+    fn get_forecast_response(
+        &self,
+        address: &str,
+        date: NaiveDate,
+        days_from_now: i64,
+    ) -> Result<TimedWeatherData> {
+        let mut url = self.base_url.join("forecast.json")?;
+        url.query_pairs_mut()
+            .append_pair("key", &self.api_key)
+            .append_pair("q", &query(address))
+            .append_pair("days", &days_from_now.to_string())
+            .append_pair("aqi", "no")
+            .append_pair("alerts", "no");
+
+        let cache_key = CacheKey::new(
+            PROVIDER_NAME,
+            Endpoint::Forecast,
+            address,
+            Some(&date.to_string()),
+        );
+        self.get_response::<TimedWeatherData>(&url, &cache_key)
+    }
+
+    fn get_history_weather_data(&self, address: &str, date: NaiveDate) -> Result<Weather> {
+        let response = self.get_history_response(address, date)?;
+        history_day(response, date)
     }
 
-    fn get_history_weather_data(&self, address: &str, date: &str) -> anyhow::Result<Weather> {
-        let mut url = Url::parse("http://api.weatherapi.com/v1/history.json")?;
+    fn get_history_response(&self, address: &str, date: NaiveDate) -> Result<TimedWeatherData> {
+        let mut url = self.base_url.join("history.json")?;
 
         url.query_pairs_mut()
             .append_pair("key", &self.api_key)
-            .append_pair("q", address)
-            .append_pair("dt", date);
+            .append_pair("q", &query(address))
+            .append_pair("dt", &date.format("%Y-%m-%d").to_string());
 
-        let response = self
-            .get_response(url.as_str())?
-            .json::<TimedWeatherData>()
-            .with_context(|| anyhow::anyhow!(WEATHER_API_ERROR))?;
+        // Today's history is still being updated, and might be today somewhere up to UTC yesterday.
+        let endpoint = if date < Utc::now().date_naive() - chrono::Duration::days(1) {
+            Endpoint::History
+        } else {
+            Endpoint::Forecast
+        };
+        let cache_key = CacheKey::new(PROVIDER_NAME, endpoint, address, Some(&date.to_string()));
+        self.get_response::<TimedWeatherData>(&url, &cache_key)
+    }
+}
+
+/// Passes coordinates through in the 'latitude,longitude' format, which weather-api accepts instead of the address.
+fn query(address: &str) -> String {
+    match address.parse::<Coordinates>() {
+        Ok(coordinates) => coordinates.to_string(),
+        Err(_) => address.to_owned(),
+    }
+}
+
+/// Extracts the only day of the history response.
+fn history_day(response: TimedWeatherData, date: NaiveDate) -> Result<Weather> {
+    let day = response
+        .forecast
+        .forecastday
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            Error::DateOutOfRange(format!(
+                "weather-api returned no data for {}. {}",
+                date, LIMITATIONS_HINT
+            ))
+        })?;
+
+    Ok(day.into_weather(response.location))
+}
 
-        Ok(Weather::FromWeatherApiTimed(response))
+/// Maps weather-api error response into the respective error category.
+/// See <https://www.weatherapi.com/docs/#intro-error-codes>.
+fn api_error(status: u16, body: &str) -> Error {
+    let error = match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(response) => response.error,
+        Err(_) => {
+            return Error::from_status(
+                status,
+                format!("weather-api responded with HTTP {}", status),
+            )
+        }
+    };
+
+    match error.code {
+        1002 | 2006 | 2008 | 2009 => Error::Credentials(error.message),
+        1003 | 1005 | 9000 | 9001 => Error::InvalidInput(error.message),
+        1006 => Error::LocationNotFound(error.message),
+        1008 => Error::DateOutOfRange(error.message),
+        2007 => Error::QuotaExceeded(error.message),
+        9999 => Error::Upstream(error.message),
+        _ if error.message.contains("date") => Error::DateOutOfRange(error.message),
+        _ => Error::from_status(status, error.message),
     }
 }
 
@@ -192,7 +735,8 @@ impl WeatherApi {
 mod tests {
     use super::*;
 
-    use crate::provider::ProviderName;
+    use crate::cache::{tests::temporary_cache, CacheTtl};
+    use crate::mock_server::{fixture, MockServer};
     use chrono::{Duration, Utc};
     use dotenvy::dotenv;
 
@@ -200,10 +744,365 @@ mod tests {
         static ref API_KEY: String = {
             let provider_name = ProviderName::WeatherApi;
             dotenv().ok();
-            std::env::var(provider_name.to_string()).expect(format!("{}_API_KEY not found in .env", provider_name).as_str())
+            std::env::var(provider_name.to_string()).unwrap_or_else(|_| panic!("{}_API_KEY not found in .env", provider_name))
         };
     }
 
+    #[test]
+    fn test_current_weather_data_into_weather() {
+        let data: CurrentWeatherData = serde_json::from_str(
+            r#"{
+                "location": {
+                    "name": "Lviv", "region": "", "country": "Ukraine",
+                    "lat": 49.83, "lon": 24.0, "tz_id": "Europe/Kiev"
+                },
+                "current": {
+                    "last_updated_epoch": 1680872400, "temp_c": 11.0, "temp_f": 51.8,
+                    "feelslike_c": 9.8, "humidity": 66, "pressure_mb": 1016.0,
+                    "wind_kph": 18.0, "wind_degree": 240,
+                    "condition": {"text": "Light rain", "code": 1183}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let weather = Weather::from(data);
+        assert_eq!(weather.location.name, "Lviv");
+        assert_eq!(weather.location.region, None);
+        assert_eq!(weather.location.country.as_deref(), Some("Ukraine"));
+        assert_eq!(weather.temperature, 11.0);
+        assert_eq!(weather.wind.speed, Some(5.0));
+        assert_eq!(weather.condition.kind, ConditionKind::Rain);
+    }
+
+    #[test]
+    fn test_api_error() {
+        let error = api_error(
+            400,
+            r#"{"error": {"code": 1006, "message": "No matching location found."}}"#,
+        );
+        assert!(matches!(error, Error::LocationNotFound(_)));
+
+        let error = api_error(
+            401,
+            r#"{"error": {"code": 2006, "message": "API key is invalid."}}"#,
+        );
+        assert!(matches!(error, Error::Credentials(_)));
+
+        let error = api_error(
+            403,
+            r#"{"error": {"code": 2007, "message": "API key has exceeded calls per month quota."}}"#,
+        );
+        assert!(matches!(error, Error::QuotaExceeded(_)));
+
+        let error = api_error(500, "Internal Server Error");
+        assert!(matches!(error, Error::Upstream(_)));
+    }
+
+    fn mock_server() -> MockServer {
+        let server = MockServer::start();
+        server
+            .route("/current.json", 200, &fixture("weather_api/current.json"))
+            .route("/forecast.json", 200, &fixture("weather_api/forecast.json"))
+            .route("/history.json", 200, &fixture("weather_api/history.json"))
+            .route("/search.json", 200, &fixture("weather_api/search.json"));
+        server
+    }
+
+    /// Today's date in the timezone of the fixtures' location.
+    fn fixture_today() -> NaiveDate {
+        Utc::now()
+            .with_timezone(&chrono_tz::Europe::Kiev)
+            .date_naive()
+    }
+
+    fn mocked_provider(server: &MockServer) -> WeatherApi {
+        WeatherApi::with_base_url(String::from("test_key"), server.url())
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked() {
+        let server = mock_server();
+        let weather = mocked_provider(&server)
+            .get_current_weather("Mykolaiv, Lviv oblast, Ukraine")
+            .unwrap();
+
+        assert_eq!(weather.location.name, "Mykolaiv");
+        assert_eq!(weather.location.timezone.as_deref(), Some("Europe/Kiev"));
+        assert_eq!(weather.temperature, 10.0);
+        assert_eq!(weather.humidity, Some(54));
+        assert_eq!(weather.precipitation, Some(0.0));
+        assert_eq!(weather.visibility, Some(10.0));
+        assert_eq!(weather.condition.kind, ConditionKind::PartlyCloudy);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("key=test_key"));
+    }
+
+    #[test]
+    fn test_geocode_mocked() {
+        let server = mock_server();
+        let provider = mocked_provider(&server);
+
+        let places = provider.geocode("Mykolaiv").unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(
+            places[1].to_string(),
+            "Mykolayiv, Mykolayivs'ka Oblast', Ukraine (46.97, 32.00)"
+        );
+        assert!(server.requests()[0].starts_with("/search.json"));
+
+        server.route("/search.json", 200, "[]");
+        assert!(matches!(
+            provider.geocode("SO INVALID ADDRESS"),
+            Err(Error::LocationNotFound(_))
+        ));
+
+        // Coordinates are returned as is.
+        let places = provider.geocode("49.84,24.03").unwrap();
+        assert_eq!(places[0].name, "49.84,24.03");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_reverse_geocode_mocked() {
+        let server = mock_server();
+        server.route(
+            "/search.json",
+            200,
+            &fixture("weather_api/search_reverse.json"),
+        );
+
+        let places = mocked_provider(&server)
+            .reverse_geocode("49.84,24.03".parse().unwrap())
+            .unwrap();
+        assert_eq!(places[0].name, "Lviv");
+        assert!(server.requests()[0].contains("q=49.84%2C24.03"));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_coordinates() {
+        let server = mock_server();
+        mocked_provider(&server)
+            .get_current_weather("49.84, 24.03")
+            .unwrap();
+
+        assert!(server.requests()[0].contains("q=49.84%2C24.03"));
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_history_cached() {
+        let server = mock_server();
+        let cache = temporary_cache(CacheTtl::default());
+        let provider = mocked_provider(&server).with_cache(cache.clone());
+
+        for _ in 0..3 {
+            let weather = provider
+                .get_timed_weather("Mykolaiv", "2023-04-06", None)
+                .unwrap();
+            assert_eq!(weather.temperature, 5.8);
+        }
+        assert_eq!(server.requests().len(), 1);
+
+        // Error responses are never cached.
+        server.route(
+            "/current.json",
+            401,
+            &fixture("weather_api/error_2006.json"),
+        );
+        assert!(provider.get_current_weather("Mykolaiv").is_err());
+        assert_eq!(cache.stats().unwrap().entries, 1);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_history() {
+        let server = mock_server();
+        let weather = mocked_provider(&server)
+            .get_timed_weather("Mykolaiv, Lviv oblast, Ukraine", "2023-04-06", None)
+            .unwrap();
+
+        assert_eq!(weather.temperature, 5.8);
+        assert_eq!(weather.condition.kind, ConditionKind::Clear);
+        assert!(server.requests()[0].contains("dt=2023-04-06"));
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_forecast() {
+        let today = fixture_today();
+        let forecast = fixture("weather_api/forecast.json")
+            .replace("2023-04-07", &today.to_string())
+            .replace("2023-04-08", &(today + Duration::days(1)).to_string())
+            .replace("2023-04-09", &(today + Duration::days(2)).to_string());
+        let server = mock_server();
+        server.route("/forecast.json", 200, &forecast);
+
+        let provider = mocked_provider(&server);
+        let weather = provider
+            .get_timed_weather("Mykolaiv", &(today + Duration::days(2)).to_string(), None)
+            .unwrap();
+        assert_eq!(weather.temperature, 4.9);
+        // Location's today is resolved from the timezone of the current weather.
+        assert!(server.requests()[0].starts_with("/current.json"));
+        assert!(server.requests()[1].contains("days=3"));
+
+        // Forecast is truncated by the subscription plan, so requested date is missing.
+        let result =
+            provider.get_timed_weather("Mykolaiv", &(today + Duration::days(5)).to_string(), None);
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
+    }
+
+    #[test]
+    fn test_get_hourly_weather_mocked() {
+        let server = mock_server();
+        let provider = mocked_provider(&server);
+        let date = NaiveDate::from_ymd_opt(2023, 4, 6).unwrap();
+
+        let hourly = provider.get_hourly_weather("Mykolaiv", date, None).unwrap();
+        assert_eq!(hourly.len(), 24);
+        assert_eq!(hourly[0].observed_at.timestamp(), 1680728400);
+        assert_eq!(hourly[0].temperature, 2.5);
+
+        // Forecast days without hourly data can't be split into hours.
+        let today = fixture_today();
+        let forecast = fixture("weather_api/forecast.json")
+            .replace("2023-04-07", &today.to_string())
+            .replace("2023-04-08", &(today + Duration::days(1)).to_string());
+        server.route("/forecast.json", 200, &forecast);
+        let result = provider.get_hourly_weather("Mykolaiv", today + Duration::days(1), None);
+        assert!(matches!(result, Err(Error::DateOutOfRange(_))));
+    }
+
+    #[test]
+    fn test_get_weather_at_mocked() {
+        let server = mock_server();
+        let provider = mocked_provider(&server);
+
+        // 15:20 in Kyiv on 2023-04-06, which is still 2023-04-06 in UTC as well.
+        let instant = Utc.timestamp_opt(1680728400 + 15 * 3600 + 1200, 0).unwrap();
+        let weather = provider.get_weather_at("Mykolaiv", instant, None).unwrap();
+        assert_eq!(weather.observed_at.timestamp(), 1680728400 + 15 * 3600);
+        assert!(server.requests().iter().any(
+            |request| request.starts_with("/history.json") && request.contains("dt=2023-04-06")
+        ));
+
+        // 00:30 in Kyiv is still the previous day in UTC, but the local day is requested.
+        let instant = Utc.timestamp_opt(1680728400 + 1800, 0).unwrap();
+        let weather = provider.get_weather_at("Mykolaiv", instant, None).unwrap();
+        assert_eq!(weather.temperature, 2.5);
+
+        assert_eq!(
+            provider.get_timezone("Mykolaiv").unwrap(),
+            chrono_tz::Europe::Kiev
+        );
+
+        // Known timezone isn't requested.
+        let server = mock_server();
+        let weather = mocked_provider(&server)
+            .get_weather_at("49.52,23.98", instant, Some(chrono_tz::Europe::Kiev))
+            .unwrap();
+        assert_eq!(weather.temperature, 2.5);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("/history.json"));
+    }
+
+    #[test]
+    fn test_get_weather_series_mocked() {
+        let today = fixture_today();
+        let forecast = fixture("weather_api/forecast.json")
+            .replace("2023-04-07", &today.to_string())
+            .replace("2023-04-08", &(today + Duration::days(1)).to_string())
+            .replace("2023-04-09", &(today + Duration::days(2)).to_string());
+        let server = mock_server();
+        server.route("/forecast.json", 200, &forecast);
+
+        let series = mocked_provider(&server)
+            .get_weather_series(
+                "Mykolaiv, Lviv oblast, Ukraine",
+                today - Duration::days(1),
+                today + Duration::days(2),
+                None,
+            )
+            .unwrap();
+        assert_eq!(series.len(), 4);
+        assert_eq!(series[3].temperature, 4.9);
+        assert!(series
+            .iter()
+            .all(|weather| weather.location.name == "Mykolaiv"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[0].starts_with("/current.json"));
+        assert!(requests[1].contains("q=Mykolaiv%2C+Lviv+oblast%2C+Ukraine"));
+        assert!(requests[2].contains("q=49.52%2C23.98"));
+        assert!(requests[3].starts_with("/forecast.json"));
+        assert!(requests[3].contains("days=3"));
+    }
+
+    #[test]
+    fn test_get_timed_weather_mocked_location_today() {
+        let server = mock_server();
+        let current =
+            fixture("weather_api/current.json").replace("Europe/Kiev", "Pacific/Kiritimati");
+        server.route("/current.json", 200, &current);
+        let provider = mocked_provider(&server);
+
+        // It's already tomorrow in Kiritimati for the most of the world, so its today is requested from the history.
+        let today = Utc::now()
+            .with_timezone(&chrono_tz::Pacific::Kiritimati)
+            .date_naive();
+        provider
+            .get_timed_weather("Kiritimati", &today.to_string(), None)
+            .unwrap();
+        let requests = server.requests();
+        assert!(requests[1].starts_with("/history.json"));
+        assert!(requests[1].contains(&format!("dt={}", today)));
+
+        // Dates, which are in the past in any timezone, don't need the location's timezone.
+        provider
+            .get_timed_weather("Kiritimati", "2023-04-06", None)
+            .unwrap();
+        assert_eq!(server.requests().len(), 3);
+
+        // Known timezone isn't requested either.
+        provider
+            .get_timed_weather(
+                "Kiritimati",
+                &today.to_string(),
+                Some(chrono_tz::Pacific::Kiritimati),
+            )
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[3].contains(&format!("dt={}", today)));
+    }
+
+    #[test]
+    fn test_get_current_weather_mocked_error_responses() {
+        let cases = [
+            (400, fixture("weather_api/error_1006.json")),
+            (401, fixture("weather_api/error_2006.json")),
+            (403, fixture("weather_api/error_2007.json")),
+            (404, String::from("Not Found")),
+            (200, String::from("{\"location\": {\"name\": ")),
+        ];
+
+        for (status, body) in cases {
+            let server = mock_server();
+            server.route("/current.json", status, &body);
+
+            let result = mocked_provider(&server).get_current_weather("Mykolaiv");
+            match status {
+                400 | 404 => assert!(matches!(result, Err(Error::LocationNotFound(_)))),
+                401 => assert!(matches!(result, Err(Error::Credentials(_)))),
+                403 => assert!(matches!(result, Err(Error::QuotaExceeded(_)))),
+                _ => assert!(matches!(result, Err(Error::Upstream(_)))),
+            }
+        }
+    }
+
     #[test]
     #[ignore]
     fn test_get_weather_api_current() {
@@ -222,7 +1121,7 @@ mod tests {
         let formatted_yesterday = yesterday.format("%Y-%m-%d");
 
         let weather =
-            provider.get_timed_weather("Odesa, Ukraine", &formatted_yesterday.to_string());
+            provider.get_timed_weather("Odesa, Ukraine", &formatted_yesterday.to_string(), None);
         assert!(weather.is_ok());
     }
 
@@ -238,6 +1137,7 @@ mod tests {
         let weather = provider.get_timed_weather(
             "Mykolaiv, Lviv oblast, Ukraine",
             &formatted_tommorow.to_string(),
+            None,
         );
         assert!(weather.is_ok());
     }
@@ -247,7 +1147,7 @@ mod tests {
     fn test_get_weather_api_timed_invalid_timestamp() {
         let provider = WeatherApi::new(API_KEY.to_string());
         let date = "088-04-01";
-        let result = provider.get_timed_weather("Mykolaiv, Lviv oblast, Ukraine", date);
+        let result = provider.get_timed_weather("Mykolaiv, Lviv oblast, Ukraine", date, None);
         assert!(result.is_err());
     }
 }
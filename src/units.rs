@@ -0,0 +1,106 @@
+//! Module for converting normalized metric weather values into the selected unit system.
+use serde::{Deserialize, Serialize};
+
+/// Unit system of the weather output, selected with the global '--units' option or stored in the configuration file.
+#[derive(
+    clap::ValueEnum,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Units {
+    /// °C, m/s, hPa, mm, km.
+    #[default]
+    Metric,
+    /// °F, mph, inHg, in, mi.
+    Imperial,
+    /// K, m/s, hPa, mm, m.
+    Standard,
+}
+
+/// Physical quantity of the weather report, which depends on the unit system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Temperature,
+    Speed,
+    Pressure,
+    Precipitation,
+    Visibility,
+}
+
+impl Units {
+    /// Converts 'value' of the 'quantity' from metric units, in which providers' data is normalized.
+    ///
+    /// # Examples
+    /// ```
+    /// use elastio_task::units::{Quantity, Units};
+    ///
+    /// assert_eq!(Units::Imperial.convert(Quantity::Temperature, 100.0), 212.0);
+    /// assert_eq!(Units::Standard.convert(Quantity::Visibility, 10.0), 10000.0);
+    /// ```
+    pub fn convert(&self, quantity: Quantity, value: f64) -> f64 {
+        let converted = match (self, quantity) {
+            (Units::Metric, _) => value,
+            (Units::Imperial, Quantity::Temperature) => value * 9.0 / 5.0 + 32.0,
+            (Units::Imperial, Quantity::Speed) => value * 3600.0 / 1609.344,
+            (Units::Imperial, Quantity::Pressure) => value / 33.8639,
+            (Units::Imperial, Quantity::Precipitation) => value / 25.4,
+            (Units::Imperial, Quantity::Visibility) => value / 1.609344,
+            (Units::Standard, Quantity::Temperature) => value + 273.15,
+            (Units::Standard, Quantity::Visibility) => value * 1000.0,
+            (Units::Standard, _) => value,
+        };
+
+        // Rounding hides floating point noise, e.g. 49.352000000000004 °F.
+        (converted * 100.0).round() / 100.0
+    }
+
+    /// Returns label of the 'quantity' unit, e.g. '°C'.
+    pub fn label(&self, quantity: Quantity) -> &'static str {
+        match (self, quantity) {
+            (Units::Metric, Quantity::Temperature) => "°C",
+            (Units::Imperial, Quantity::Temperature) => "°F",
+            (Units::Standard, Quantity::Temperature) => "K",
+            (Units::Imperial, Quantity::Speed) => "mph",
+            (_, Quantity::Speed) => "m/s",
+            (Units::Imperial, Quantity::Pressure) => "inHg",
+            (_, Quantity::Pressure) => "hPa",
+            (Units::Imperial, Quantity::Precipitation) => "in",
+            (_, Quantity::Precipitation) => "mm",
+            (Units::Metric, Quantity::Visibility) => "km",
+            (Units::Imperial, Quantity::Visibility) => "mi",
+            (Units::Standard, Quantity::Visibility) => "m",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(Units::Metric.convert(Quantity::Temperature, 9.64), 9.64);
+        assert_eq!(Units::Imperial.convert(Quantity::Temperature, 9.64), 49.35);
+        assert_eq!(Units::Standard.convert(Quantity::Temperature, 9.64), 282.79);
+        assert_eq!(Units::Imperial.convert(Quantity::Speed, 4.12), 9.22);
+        assert_eq!(Units::Imperial.convert(Quantity::Pressure, 1016.0), 30.0);
+        assert_eq!(Units::Imperial.convert(Quantity::Precipitation, 25.4), 1.0);
+        assert_eq!(Units::Imperial.convert(Quantity::Visibility, 10.0), 6.21);
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(Units::Imperial.label(Quantity::Speed), "mph");
+        assert_eq!(Units::Standard.label(Quantity::Temperature), "K");
+        assert_eq!(Units::Metric.label(Quantity::Visibility), "km");
+    }
+}